@@ -1,5 +1,8 @@
 import type { Engine } from "../wasm/pkg/nbody_collisions";
 
+// only engines built with the `dim3` feature can orbit the camera
+type OrbitEngine = Engine & { orbit_camera(deltaX: number, deltaY: number): void };
+
 export class InteractionHandler {
     private device: GPUDevice;
     private canvas: HTMLCanvasElement;
@@ -28,6 +31,7 @@ export class InteractionHandler {
         this.addResizeListener();
         this.addZoomListener();
         this.addPanListeners();
+        this.addInspectListener();
     }
 
     private addResizeListener() {
@@ -99,7 +103,12 @@ export class InteractionHandler {
             const deltaY = canvasY - this.lastMouseCanvasPos[1];
             this.lastMouseCanvasPos = [canvasX, canvasY];
 
-            this.engine.pan_camera(-deltaX, -deltaY);
+            // 3D builds orbit on drag and pan on shift-drag
+            if ("orbit_camera" in this.engine && !e.shiftKey) {
+                (this.engine as OrbitEngine).orbit_camera(deltaX, deltaY);
+            } else {
+                this.engine.pan_camera(-deltaX, -deltaY);
+            }
         });
 
         const endPan = (e: PointerEvent) => {
@@ -117,4 +126,26 @@ export class InteractionHandler {
         this.canvas.addEventListener("pointerup", endPan);
         this.canvas.addEventListener("pointercancel", endPan);
     }
-}
\ No newline at end of file
+
+    // double clicking a body shows its state in the inspector panel, double clicking empty space hides it
+    private addInspectListener() {
+        const inspector = document.getElementById("inspector");
+        if (!inspector) {
+            return;
+        }
+        this.canvas.addEventListener("dblclick", (e) => {
+            const [px, py] = this.clientToCanvasCoords(e.clientX, e.clientY);
+            const id = this.engine.pick_body(px, py);
+            const body = id !== undefined ? this.engine.get_body(id) : undefined;
+            inspector.hidden = body === undefined;
+            if (body !== undefined) {
+                inspector.textContent = formatBody(body);
+            }
+        });
+    }
+}
+
+// the body's fields one per line, with numbers rounded for display
+function formatBody(body: object): string {
+    return JSON.stringify(body, (_, value) => typeof value === "number" ? Number(value.toPrecision(6)) : value, 2);
+}
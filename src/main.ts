@@ -1,3 +1,4 @@
+import * as wasm from '../wasm/pkg/nbody_collisions';
 import { Engine } from '../wasm/pkg/nbody_collisions';
 import { initDeviceAndContext } from './gpuSetup';
 import { InteractionHandler } from './interaction';
@@ -9,7 +10,13 @@ const DELTA_TIME = 1 / (FPS * SUBSTEPS);
 async function main() {
     const { device, canvas, context, canvasFormat } = await initDeviceAndContext('canvas');
 
-    const engine = await Engine.create();
+    // only exported by builds with the `parallel` feature, see wasm/src/lib.rs
+    if ('initThreadPool' in wasm) {
+        const initThreadPool = wasm.initThreadPool as (numThreads: number) => Promise<void>;
+        await initThreadPool(navigator.hardwareConcurrency);
+    }
+
+    const engine = await Engine.create({});
     const interactionHandler = new InteractionHandler(device, canvas, context, canvasFormat, engine);
 
     function frame() {
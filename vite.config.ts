@@ -2,7 +2,15 @@ import { defineConfig } from "vite";
 import wasm from "vite-plugin-wasm";
 // import topLevelAwait from "vite-plugin-top-level-await";
 
+// cross-origin isolation, required for the shared memory of multithreaded wasm builds
+const headers = {
+  "Cross-Origin-Opener-Policy": "same-origin",
+  "Cross-Origin-Embedder-Policy": "require-corp",
+};
+
 export default defineConfig({
+  server: { headers },
+  preview: { headers },
   plugins: [
     wasm(),
 
@@ -0,0 +1,51 @@
+//! Direct-sum gravity throughput of the scalar array-of-structs kernel against the vectorized
+//! structure-of-arrays one, including the sync of arrays kept across calls as the simulation does.
+//! Run with `cargo bench --bench gravity` (add `--features parallel` to include threading).
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use nbody_collisions::simulation::{
+    BodyArrays, Boundary, Gravity, GravitySolver, RandomSquare, Scenario, Simulation, SimulationConfig, Softening,
+};
+
+/// Calls `f` repeatedly for at least half a second and returns the mean time per call.
+fn time(mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    let mut runs = 0;
+    while runs == 0 || start.elapsed() < Duration::from_millis(500) {
+        f();
+        runs += 1;
+    }
+    start.elapsed() / runs
+}
+
+fn main() {
+    let gravity = Gravity {
+        grav_constant: 600.0,
+        softening: Softening::Plummer { length: 0.03 },
+        solver: GravitySolver::Direct,
+    };
+    println!("{:>8} {:>12} {:>12} {:>8}", "bodies", "scalar", "simd", "speedup");
+    for num_bodies in [1_000, 2_000, 5_000, 10_000] {
+        let scenario = Scenario::RandomSquare(RandomSquare { num_bodies, ..RandomSquare::default() });
+        let config = SimulationConfig { seed: Some(1), scenario, ..SimulationConfig::default() };
+        let bodies = Simulation::from_config(&config).expect("valid config").get_bodies().to_vec();
+        let mut arrays = BodyArrays::new(&bodies);
+
+        let scalar = time(|| {
+            black_box(gravity.direct_accelerations(black_box(&bodies), &Boundary::Open));
+        });
+        let simd = time(|| {
+            arrays.sync(black_box(&bodies));
+            black_box(gravity.accelerations_with(black_box(&bodies), &Boundary::Open, &arrays));
+        });
+        println!(
+            "{:>8} {:>10.2}ms {:>10.2}ms {:>7.1}x",
+            num_bodies,
+            scalar.as_secs_f64() * 1e3,
+            simd.as_secs_f64() * 1e3,
+            scalar.as_secs_f64() / simd.as_secs_f64()
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use wasm_bindgen::prelude::*;
 
-use crate::simulation::Simulation;
+use crate::simulation::{Body, BodyId, BodyKind, Boundary, CollisionMode, ContactSolverSettings, Coulomb, ForceField, ForceLawKind, Fragmentation, GravitySolver, LennardJones, Material, Scalar, Scenario, Simulation, SimulationConfig, Softening, Spring, Vector, Yukawa};
 use crate::renderer::Renderer;
 
 #[wasm_bindgen]
@@ -9,22 +9,275 @@ struct Engine {
     renderer: Renderer<'static>,
 }
 
+/// Snapshot of the simulation diagnostics, flattened for javascript.
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+struct DiagnosticsReport {
+    pub kinetic_energy: Scalar,
+    pub gravitational_energy: Scalar,
+    pub interaction_energy: Scalar,
+    pub external_energy: Scalar,
+    pub total_energy: Scalar,
+    pub momentum_x: Scalar,
+    pub momentum_y: Scalar,
+    #[cfg(feature = "dim3")]
+    pub momentum_z: Scalar,
+    #[cfg(feature = "dim3")]
+    pub angular_momentum_x: Scalar,
+    #[cfg(feature = "dim3")]
+    pub angular_momentum_y: Scalar,
+    /// About the z axis (the z component in 3D).
+    pub angular_momentum: Scalar,
+    pub center_of_mass_x: Scalar,
+    pub center_of_mass_y: Scalar,
+    #[cfg(feature = "dim3")]
+    pub center_of_mass_z: Scalar,
+    pub total_mass: Scalar,
+    pub virial_ratio: Scalar,
+}
+
 // engine functions exposed to javascript
 #[wasm_bindgen]
 impl Engine {
 
     #[allow(unused)] // compiler thinks "static_method_of" is a (unused) variable for some reason
     #[wasm_bindgen(static_method_of = Engine)]
-    pub async fn create() -> Engine {
-        let simulation = Simulation::new();
-        let renderer = Renderer::new().await;
-        Engine { simulation, renderer }
+    pub async fn create(config: JsValue) -> Result<Engine, JsError> {
+        // a plain object with any subset of the `SimulationConfig` fields, or undefined for defaults
+        let config: SimulationConfig = if config.is_undefined() || config.is_null() {
+            SimulationConfig::default()
+        } else {
+            serde_wasm_bindgen::from_value(config)?
+        };
+        let simulation = Simulation::from_config(&config)?;
+        let mut renderer = Renderer::new().await;
+        renderer.set_boundary(simulation.get_boundary());
+        Ok(Engine { simulation, renderer })
+    }
+
+    pub fn load_scenario(&mut self, scenario: JsValue) -> Result<(), JsError> {
+        // e.g. { type: "PlummerSphere", num_bodies: 2000 }, restarting with the settings suited to it
+        let scenario: Scenario = serde_wasm_bindgen::from_value(scenario)?;
+        let config = SimulationConfig { seed: Some(self.simulation.seed()), ..scenario.config() };
+        self.simulation = Simulation::from_config(&config)?;
+        self.renderer.set_boundary(self.simulation.get_boundary());
+        Ok(())
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.simulation.seed()
     }
 
-    pub fn update(&mut self, dt: f32) {
+    pub fn update(&mut self, dt: Scalar) {
         self.simulation.update(dt);
     }
 
+    pub fn use_direct_gravity(&mut self) -> Result<(), JsError> {
+        Ok(self.simulation.set_gravity_solver(GravitySolver::Direct)?)
+    }
+    pub fn use_barnes_hut(&mut self, theta: Scalar) -> Result<(), JsError> {
+        Ok(self.simulation.set_gravity_solver(GravitySolver::BarnesHut { theta })?)
+    }
+    pub fn use_plummer_softening(&mut self, length: Scalar) -> Result<(), JsError> {
+        Ok(self.simulation.set_softening(Softening::Plummer { length })?)
+    }
+    pub fn use_spline_softening(&mut self, length: Scalar) -> Result<(), JsError> {
+        Ok(self.simulation.set_softening(Softening::Spline { length })?)
+    }
+    pub fn gravity_error(&self, theta: Scalar) -> Scalar {
+        self.simulation.gravity_error(GravitySolver::BarnesHut { theta })
+    }
+    pub fn use_bounce_collisions(&mut self) -> Result<(), JsError> {
+        Ok(self.simulation.set_collision_mode(CollisionMode::Bounce)?)
+    }
+    pub fn use_merge_collisions(&mut self) -> Result<(), JsError> {
+        Ok(self.simulation.set_collision_mode(CollisionMode::Merge)?)
+    }
+    pub fn set_solver_iterations(&mut self, iterations: u32) -> Result<(), JsError> {
+        let settings = ContactSolverSettings { iterations, ..self.simulation.contact_solver_settings() };
+        Ok(self.simulation.set_contact_solver_settings(settings)?)
+    }
+    // materials are objects with the fields of `Material`, missing ones taking their default, e.g.
+    // { restitution: 0.2, friction_combine: "Max" }
+    pub fn add_material(&mut self, material: JsValue) -> Result<u32, JsError> {
+        let material: Material = serde_wasm_bindgen::from_value(material)?;
+        Ok(self.simulation.add_material(material)?)
+    }
+    pub fn set_material(&mut self, index: u32, material: JsValue) -> Result<(), JsError> {
+        let material: Material = serde_wasm_bindgen::from_value(material)?;
+        Ok(self.simulation.set_material(index, material)?)
+    }
+    // bodies are addressed by the ids returned from `add_body` (or read from `get_body`), which
+    // stay valid while other bodies are added, removed or merged
+    pub fn add_body(&mut self, x: Scalar, y: Scalar, velocity_x: Scalar, velocity_y: Scalar, mass: Scalar, radius: Scalar) -> Result<u32, JsError> {
+        let body = Body::new(Vector::planar(x, y), Vector::planar(velocity_x, velocity_y), mass, radius);
+        Ok(self.simulation.add_body(body)?.0)
+    }
+    pub fn remove_body(&mut self, id: u32) -> bool {
+        self.simulation.remove_body(BodyId(id)).is_some()
+    }
+    pub fn get_body(&self, id: u32) -> Result<JsValue, JsError> {
+        // a plain object with the body's fields, or undefined if it no longer exists
+        match self.simulation.get_body(BodyId(id)) {
+            Some(body) => Ok(serde_wasm_bindgen::to_value(body)?),
+            None => Ok(JsValue::UNDEFINED),
+        }
+    }
+    // spatial queries take pixel coordinates on the canvas and return body ids. In 3D, points are
+    // taken on the plane through the camera's target, facing the camera
+    pub fn pick_body(&self, px: f32, py: f32) -> Option<u32> {
+        #[cfg(not(feature = "dim3"))]
+        let id = self.simulation.body_at(self.renderer.screen_to_world(px, py));
+        // the nearest body under the cursor, wherever it is along the view direction
+        #[cfg(feature = "dim3")]
+        let id = {
+            let (origin, direction) = self.renderer.screen_ray(px, py);
+            self.simulation.body_on_ray(origin, direction)
+        };
+        id.map(|id| id.0)
+    }
+    pub fn bodies_within(&self, px: f32, py: f32, radius_px: f32) -> Vec<u32> {
+        let center = self.renderer.screen_to_world(px, py);
+        let radius = self.renderer.screen_to_world_length(radius_px) as Scalar;
+        self.simulation.bodies_within(center, radius).into_iter().map(|id| id.0).collect()
+    }
+    pub fn nearest_bodies(&self, px: f32, py: f32, k: usize) -> Vec<u32> {
+        let point = self.renderer.screen_to_world(px, py);
+        self.simulation.nearest_bodies(point, k).into_iter().map(|id| id.0).collect()
+    }
+    #[cfg(not(feature = "dim3"))]
+    pub fn bodies_in_rect(&self, px0: f32, py0: f32, px1: f32, py1: f32) -> Vec<u32> {
+        // corners in any order, e.g. the start and end of a drag
+        let (a, b) = (self.renderer.screen_to_world(px0, py0), self.renderer.screen_to_world(px1, py1));
+        self.simulation.bodies_in_rect(a.min(b), a.max(b)).into_iter().map(|id| id.0).collect()
+    }
+    /// Bodies whose center appears inside the given screen rectangle, at any depth.
+    #[cfg(feature = "dim3")]
+    pub fn bodies_in_rect(&self, px0: f32, py0: f32, px1: f32, py1: f32) -> Vec<u32> {
+        let frustum = self.renderer.frustum(px0, py0, px1, py1);
+        self.simulation.bodies_in_planes(&frustum).into_iter().map(|id| id.0).collect()
+    }
+    pub fn set_body_material(&mut self, id: u32, material: u32) -> Result<(), JsError> {
+        Ok(self.simulation.set_body_material(BodyId(id), material)?)
+    }
+    pub fn set_body_charge(&mut self, id: u32, charge: Scalar) {
+        self.simulation.set_body_charge(BodyId(id), charge);
+    }
+    pub fn set_body_static(&mut self, id: u32) {
+        self.simulation.set_body_kind(BodyId(id), BodyKind::Static);
+    }
+    pub fn set_body_kinematic(&mut self, id: u32, velocity_x: Scalar, velocity_y: Scalar) {
+        self.simulation.set_body_kind(BodyId(id), BodyKind::Kinematic);
+        self.simulation.set_body_velocity(BodyId(id), Vector::planar(velocity_x, velocity_y));
+    }
+    pub fn set_body_dynamic(&mut self, id: u32) {
+        self.simulation.set_body_kind(BodyId(id), BodyKind::Dynamic);
+    }
+    pub fn move_kinematic_body(&mut self, id: u32, x: Scalar, y: Scalar, dt: Scalar) {
+        self.simulation.move_kinematic_body(BodyId(id), Vector::planar(x, y), dt);
+    }
+    // containers are made of the given material index (0 for the default material)
+    pub fn set_box_container(&mut self, min_x: Scalar, min_y: Scalar, max_x: Scalar, max_y: Scalar, material: u32) -> Result<(), JsError> {
+        let (min, max) = planar_box(min_x, min_y, max_x, max_y);
+        let boundary = Boundary::Box { min, max, material };
+        self.set_boundary(boundary)
+    }
+    pub fn set_circle_container(&mut self, center_x: Scalar, center_y: Scalar, radius: Scalar, material: u32) -> Result<(), JsError> {
+        let boundary = Boundary::Circle { center: Vector::planar(center_x, center_y), radius, material };
+        self.set_boundary(boundary)
+    }
+    pub fn set_periodic_domain(&mut self, min_x: Scalar, min_y: Scalar, max_x: Scalar, max_y: Scalar) -> Result<(), JsError> {
+        let (min, max) = planar_box(min_x, min_y, max_x, max_y);
+        let boundary = Boundary::Periodic { min, max };
+        self.set_boundary(boundary)
+    }
+    pub fn remove_container(&mut self) {
+        self.set_boundary(Boundary::Open).expect("open space is valid");
+    }
+    pub fn add_uniform_gravity(&mut self, acceleration_x: Scalar, acceleration_y: Scalar) -> Result<(), JsError> {
+        let acceleration = Vector::planar(acceleration_x, acceleration_y);
+        Ok(self.simulation.add_force_field(ForceField::Uniform { acceleration })?)
+    }
+    pub fn add_point_mass(&mut self, x: Scalar, y: Scalar, mass: Scalar) -> Result<(), JsError> {
+        Ok(self.simulation.add_force_field(ForceField::PointMass { center: Vector::planar(x, y), mass })?)
+    }
+    pub fn add_logarithmic_halo(
+        &mut self,
+        x: Scalar,
+        y: Scalar,
+        circular_velocity: Scalar,
+        core_radius: Scalar,
+    ) -> Result<(), JsError> {
+        let field = ForceField::LogarithmicHalo { center: Vector::planar(x, y), circular_velocity, core_radius };
+        Ok(self.simulation.add_force_field(field)?)
+    }
+    pub fn add_nfw_halo(&mut self, x: Scalar, y: Scalar, mass: Scalar, scale_radius: Scalar) -> Result<(), JsError> {
+        let field = ForceField::NfwHalo { center: Vector::planar(x, y), mass, scale_radius };
+        Ok(self.simulation.add_force_field(field)?)
+    }
+    pub fn add_linear_drag(&mut self, coefficient: Scalar) -> Result<(), JsError> {
+        Ok(self.simulation.add_force_field(ForceField::LinearDrag { coefficient })?)
+    }
+    pub fn add_quadratic_drag(&mut self, coefficient: Scalar) -> Result<(), JsError> {
+        Ok(self.simulation.add_force_field(ForceField::QuadraticDrag { coefficient })?)
+    }
+    pub fn clear_force_fields(&mut self) {
+        self.simulation.clear_force_fields();
+    }
+    // pairwise force laws on top of gravity, set grav_constant to 0 in the config for these alone
+    pub fn add_coulomb_force(&mut self, coulomb_constant: Scalar, softening_length: Scalar) -> Result<(), JsError> {
+        let softening = if softening_length > 0.0 { Softening::Plummer { length: softening_length } } else { Softening::None };
+        Ok(self.simulation.add_force_law(ForceLawKind::Coulomb(Coulomb { coulomb_constant, softening }))?)
+    }
+    pub fn add_lennard_jones_force(&mut self, epsilon: Scalar, sigma: Scalar, cutoff: Scalar) -> Result<(), JsError> {
+        Ok(self.simulation.add_force_law(ForceLawKind::LennardJones(LennardJones { epsilon, sigma, cutoff }))?)
+    }
+    pub fn add_yukawa_force(&mut self, strength: Scalar, screening_length: Scalar) -> Result<(), JsError> {
+        Ok(self.simulation.add_force_law(ForceLawKind::Yukawa(Yukawa { strength, screening_length }))?)
+    }
+    pub fn add_spring_force(&mut self, stiffness: Scalar, rest_length: Scalar, cutoff: Scalar) -> Result<(), JsError> {
+        Ok(self.simulation.add_force_law(ForceLawKind::Spring(Spring { stiffness, rest_length, cutoff }))?)
+    }
+    pub fn clear_force_laws(&mut self) {
+        self.simulation.clear_force_laws();
+    }
+    pub fn set_continuous_collisions(&mut self, enabled: bool) {
+        self.simulation.set_continuous_collisions(enabled);
+    }
+    pub fn use_fragment_collisions(&mut self, energy_threshold: Scalar) -> Result<(), JsError> {
+        let params = Fragmentation { energy_threshold, ..Fragmentation::default() };
+        Ok(self.simulation.set_collision_mode(CollisionMode::Fragment(params))?)
+    }
+
+    pub fn diagnostics(&self) -> DiagnosticsReport {
+        let d = self.simulation.diagnostics();
+        DiagnosticsReport {
+            kinetic_energy: d.kinetic_energy,
+            gravitational_energy: d.gravitational_energy,
+            interaction_energy: d.interaction_energy,
+            external_energy: d.external_energy,
+            total_energy: d.total_energy,
+            momentum_x: d.momentum.x,
+            momentum_y: d.momentum.y,
+            #[cfg(feature = "dim3")]
+            momentum_z: d.momentum.z,
+            #[cfg(feature = "dim3")]
+            angular_momentum_x: d.angular_momentum.x,
+            #[cfg(feature = "dim3")]
+            angular_momentum_y: d.angular_momentum.y,
+            #[cfg(not(feature = "dim3"))]
+            angular_momentum: d.angular_momentum,
+            #[cfg(feature = "dim3")]
+            angular_momentum: d.angular_momentum.z,
+            center_of_mass_x: d.center_of_mass.x,
+            center_of_mass_y: d.center_of_mass.y,
+            #[cfg(feature = "dim3")]
+            center_of_mass_z: d.center_of_mass.z,
+            total_mass: d.total_mass,
+            virial_ratio: d.virial_ratio,
+        }
+    }
+
     pub fn transfer_bodies_to_renderer(&mut self) {
         self.renderer.fill_bodies_buffer(&self.simulation.get_bodies());
     }
@@ -43,7 +296,31 @@ impl Engine {
     }
 }
 
+// 3D-only engine functions exposed to javascript
+#[cfg(feature = "dim3")]
+#[wasm_bindgen]
+impl Engine {
+    pub fn orbit_camera(&mut self, delta_x: f32, delta_y: f32) {
+        self.renderer.orbit_camera(delta_x, delta_y);
+    }
+}
+
 // internal engine functions
 impl Engine {
-    // todo
-}
\ No newline at end of file
+    fn set_boundary(&mut self, boundary: Boundary) -> Result<(), JsError> {
+        self.simulation.set_boundary(boundary)?;
+        self.renderer.set_boundary(&boundary);
+        Ok(())
+    }
+}
+
+/// Corners of a box given by its extent in the xy plane. In 3D it spans the same range along z as
+/// along x, as the 2D-style API has no z coordinates.
+#[cfg(not(feature = "dim3"))]
+fn planar_box(min_x: Scalar, min_y: Scalar, max_x: Scalar, max_y: Scalar) -> (Vector, Vector) {
+    (Vector::new(min_x, min_y), Vector::new(max_x, max_y))
+}
+#[cfg(feature = "dim3")]
+fn planar_box(min_x: Scalar, min_y: Scalar, max_x: Scalar, max_y: Scalar) -> (Vector, Vector) {
+    (Vector::new(min_x, min_y, min_x), Vector::new(max_x, max_y, max_x))
+}
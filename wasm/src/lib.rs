@@ -1,11 +1,25 @@
+#[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
 // wasm-pack build --features console_error_panic_hook (feature is optional)
 
+// multithreaded build, rayon on wasm needs shared memory and a std rebuilt with atomics (nightly):
+// (RUSTFLAGS replaces the flags in .cargo/config.toml, so simd128 is repeated here)
+// RUSTFLAGS='-C target-feature=+atomics,+bulk-memory,+simd128' rustup run nightly \
+//     wasm-pack build --features parallel -- -Z build-std=panic_abort,std
+// javascript must then call `initThreadPool(navigator.hardwareConcurrency)` before creating the engine
+#[cfg(all(target_arch = "wasm32", feature = "parallel"))]
+pub use wasm_bindgen_rayon::init_thread_pool;
+
+// the engine and renderer need a browser canvas, so only the simulation builds natively (e.g. for
+// `cargo test` and reproducibility checks)
+#[cfg(target_arch = "wasm32")]
 mod engine;
-mod simulation;
+pub mod simulation;
+#[cfg(target_arch = "wasm32")]
 mod renderer;
 
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen(start)]
 pub fn start() {
     
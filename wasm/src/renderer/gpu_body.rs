@@ -0,0 +1,72 @@
+use bytemuck::{Pod, Zeroable};
+
+use crate::simulation::Body;
+
+/// A body as laid out in the shader's bodies buffer, in f32 whatever the simulation's `Scalar`.
+#[cfg(not(feature = "dim3"))]
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct GpuBody {
+    pub position: [f32; 2],
+    pub velocity: [f32; 2],
+    pub mass: f32,
+    pub radius: f32,
+    pub angle: f32,
+    pub angular_velocity: f32,
+    pub material: u32,
+    pub kind: u32, // BodyKind: 0 = dynamic, 1 = static, 2 = kinematic
+    pub id: u32,
+    pub _pad: u32, // struct size must be a multiple of 8 (alignment of vec2f)
+}
+
+/// A 3D body as laid out in the shader's bodies buffer. Spin isn't drawn, spheres look the same
+/// from every side.
+#[cfg(feature = "dim3")]
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct GpuBody {
+    pub position: [f32; 3],
+    pub radius: f32, // packed after position, vec3f is 16-byte aligned
+    pub velocity: [f32; 3],
+    pub mass: f32,
+    pub material: u32,
+    pub kind: u32, // BodyKind: 0 = dynamic, 1 = static, 2 = kinematic
+    pub id: u32,
+    pub _pad: u32, // struct size must be a multiple of 16 (alignment of vec3f)
+}
+
+#[cfg(not(feature = "dim3"))]
+impl From<&Body> for GpuBody {
+    #[allow(clippy::unnecessary_cast)] // `Scalar` is already f32 without the f64 feature
+    fn from(body: &Body) -> Self {
+        GpuBody {
+            position: [body.position.x as f32, body.position.y as f32],
+            velocity: [body.velocity.x as f32, body.velocity.y as f32],
+            mass: body.mass as f32,
+            radius: body.radius as f32,
+            angle: body.angle as f32,
+            angular_velocity: body.angular_velocity as f32,
+            material: body.material,
+            kind: body.kind as u32,
+            id: body.id.0,
+            _pad: 0,
+        }
+    }
+}
+
+#[cfg(feature = "dim3")]
+impl From<&Body> for GpuBody {
+    #[allow(clippy::unnecessary_cast)] // `Scalar` is already f32 without the f64 feature
+    fn from(body: &Body) -> Self {
+        GpuBody {
+            position: body.position.axes().map(|v| v as f32),
+            radius: body.radius as f32,
+            velocity: body.velocity.axes().map(|v| v as f32),
+            mass: body.mass as f32,
+            material: body.material,
+            kind: body.kind as u32,
+            id: body.id.0,
+            _pad: 0,
+        }
+    }
+}
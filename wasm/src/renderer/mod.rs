@@ -1,5 +1,15 @@
+#[allow(clippy::module_inception)]
 mod renderer;
 mod wgpu_state;
 mod uniforms;
+mod gpu_body;
+#[cfg(not(feature = "dim3"))]
+mod view2d;
+#[cfg(not(feature = "dim3"))]
+use view2d as view;
+#[cfg(feature = "dim3")]
+mod view3d;
+#[cfg(feature = "dim3")]
+use view3d as view;
 
 pub use renderer::Renderer;
\ No newline at end of file
@@ -1,22 +1,28 @@
+use bytemuck::Zeroable;
 use wasm_bindgen::JsCast;
 
 use crate::renderer::wgpu_state::WgpuState;
 use crate::renderer::uniforms::Uniforms;
+use crate::renderer::gpu_body::GpuBody;
+use crate::renderer::view::{self, Camera};
 use crate::simulation;
 
-const SHADER_CODE: &str = include_str!("shaders/render.wgsl");
-
 /// Renderer struct responsible for all rendering. Manages wgpu state and rendering pipeline(s).
 pub struct Renderer<'window> {
     // wgpu state and resources
     wgpu_state: WgpuState<'window>,
 
-    // render pipeline and bind groups/layouts
+    // render pipelines (bodies and container outline) and bind groups/layouts
     render_pipeline: wgpu::RenderPipeline,
+    boundary_pipeline: wgpu::RenderPipeline,
     bind_group_layout: wgpu::BindGroupLayout,
     bind_group: wgpu::BindGroup,
 
-    // uniforms (viewport, cam_center, cam_half_size, num_bodies)
+    // depth buffer, if the view uses one
+    depth_view: Option<wgpu::TextureView>,
+
+    // camera, and uniforms (camera, num_bodies, boundary)
+    camera: Camera,
     uniforms: Uniforms,
     uniforms_buffer: wgpu::Buffer,
 
@@ -43,19 +49,15 @@ impl Renderer<'_> {
 
 
         // create buffers
-        let uniforms = Uniforms {
-            view_port: [width, height],
-            cam_center: [0.0, 0.0],
-            cam_half_size: [10.0, 10.0],
-            num_bodies: 0,
-            _pad: 0,
-        };
+        let camera = Camera::new(width, height);
+        let mut uniforms = Uniforms::zeroed();
+        camera.apply(&mut uniforms);
 
         // initialize bodies buffer
         let capacity_bodies = 1;
         let bodies_buffer = wgpu_state.device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("bodies buffer"),
-            size: (capacity_bodies as u64) * std::mem::size_of::<simulation::Body>() as u64,
+            size: (capacity_bodies as u64) * std::mem::size_of::<GpuBody>() as u64,
             usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
@@ -72,7 +74,7 @@ impl Renderer<'_> {
         // create render pipeline and bind group/layout
         let shader = wgpu_state.device.create_shader_module(wgpu::ShaderModuleDescriptor { 
             label: Some("render shader"), 
-            source: wgpu::ShaderSource::Wgsl(SHADER_CODE.into())
+            source: wgpu::ShaderSource::Wgsl(view::SHADER_CODE.into())
         });
 
         let bind_group_layout = wgpu_state.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -80,7 +82,7 @@ impl Renderer<'_> {
             entries: &[
                 wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
@@ -123,33 +125,51 @@ impl Renderer<'_> {
         });
 
         let swapchain_format = wgpu_state.surface.get_capabilities(&wgpu_state.adapter).formats[0];
-        let render_pipeline = wgpu_state.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor { 
-            label: Some("render pipeline"),
-            layout: Some(&pipeline_layout), 
-            vertex: wgpu::VertexState {
-                module: &shader, 
-                entry_point: Some("vertex_main"), 
-                buffers: &[],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }, 
-            fragment: Some(wgpu::FragmentState {
-                module: &shader, 
-                entry_point: Some("fragment_main"), 
-                compilation_options: wgpu::PipelineCompilationOptions::default(), 
-                targets: &[Some(swapchain_format.into())],
-            }),
-            primitive: wgpu::PrimitiveState::default(), 
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),  
-            multiview_mask: None, 
-            cache: None, 
-        });
+        let create_pipeline = |label, vertex_entry, fragment_entry, topology| {
+            wgpu_state.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor { 
+                label: Some(label),
+                layout: Some(&pipeline_layout), 
+                vertex: wgpu::VertexState {
+                    module: &shader, 
+                    entry_point: Some(vertex_entry), 
+                    buffers: &[],
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }, 
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader, 
+                    entry_point: Some(fragment_entry), 
+                    compilation_options: wgpu::PipelineCompilationOptions::default(), 
+                    targets: &[Some(swapchain_format.into())],
+                }),
+                primitive: wgpu::PrimitiveState { topology, ..Default::default() },
+                depth_stencil: view::DEPTH_FORMAT.map(|format| wgpu::DepthStencilState {
+                    format,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Greater,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState::default(),  
+                multiview_mask: None, 
+                cache: None, 
+            })
+        };
+        let render_pipeline = create_pipeline(
+            "render pipeline", "vertex_main", "fragment_main", wgpu::PrimitiveTopology::TriangleList,
+        );
+        let boundary_pipeline = create_pipeline(
+            "boundary pipeline", "boundary_vertex_main", "boundary_fragment_main", view::BOUNDARY_TOPOLOGY,
+        );
+        let depth_view = create_depth_view(&wgpu_state.device, width, height);
 
         Renderer {
             wgpu_state,
             render_pipeline,
+            boundary_pipeline,
             bind_group_layout,
             bind_group,
+            depth_view,
+            camera,
             uniforms,
             uniforms_buffer,
             capacity_bodies,
@@ -162,6 +182,11 @@ impl Renderer<'_> {
         self.wgpu_state.queue.write_buffer(&self.uniforms_buffer, 0, uniforms_bytes);
     }
 
+    fn update_camera(&mut self) {
+        self.camera.apply(&mut self.uniforms);
+        self.update_uniforms_buffer();
+    }
+
     fn recreate_bind_group(&mut self) {
         self.bind_group = self.wgpu_state.device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("bind group"),
@@ -206,14 +231,32 @@ impl Renderer<'_> {
                         store: wgpu::StoreOp::Store, 
                     }, 
                 })],
-                depth_stencil_attachment: None, 
+                depth_stencil_attachment: self.depth_view.as_ref().map(|view| {
+                    wgpu::RenderPassDepthStencilAttachment {
+                        view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(0.0),
+                            store: wgpu::StoreOp::Discard,
+                        }),
+                        stencil_ops: None,
+                    }
+                }),
                 timestamp_writes: None, 
                 occlusion_query_set: None, 
                 multiview_mask: None, 
             });
-            render_pass.set_pipeline(&self.render_pipeline);
             render_pass.set_bind_group(0, &self.bind_group, &[]);
-            render_pass.draw(0..6, 0..self.uniforms.num_bodies);
+
+            // container outline first, so bodies are drawn on top
+            let boundary_vertices = view::boundary_vertices(self.uniforms.boundary_kind);
+            if boundary_vertices != 0 {
+                render_pass.set_pipeline(&self.boundary_pipeline);
+                render_pass.draw(0..boundary_vertices, 0..1);
+            }
+
+            let tiles = view::tiles(self.uniforms.boundary_kind);
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.draw(0..6, 0..self.uniforms.num_bodies * tiles);
         }
 
         self.wgpu_state.queue.submit([encoder.finish()]);
@@ -221,7 +264,9 @@ impl Renderer<'_> {
     }
 
     // todo: change [f32; 4] to Body struct when defined
-    /// Fills the bodies buffer with given bodies data, so it can be rendered by the GPU.
+    /// Fills the bodies buffer with given bodies data (converted to f32), so it can be rendered by
+    /// the GPU. The body count may shrink between calls (e.g. after merges); only the first
+    /// `num_bodies` are drawn.
     pub fn fill_bodies_buffer(&mut self, bodies: &[simulation::Body]) {
         self.uniforms.num_bodies = bodies.len() as u32;
 
@@ -230,7 +275,7 @@ impl Renderer<'_> {
             self.capacity_bodies = (self.uniforms.num_bodies as f32 * 1.5).ceil() as u32;
             self.bodies_buffer = self.wgpu_state.device.create_buffer(&wgpu::BufferDescriptor {
                 label: Some("bodies buffer"),
-                size: (self.capacity_bodies as u64) * std::mem::size_of::<simulation::Body>() as u64,
+                size: (self.capacity_bodies as u64) * std::mem::size_of::<GpuBody>() as u64,
                 usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
                 mapped_at_creation: false,
             });
@@ -238,50 +283,85 @@ impl Renderer<'_> {
         }
 
         // upload data to buffer
-        let data_bytes = bytemuck::cast_slice(&bodies[..]);
+        let gpu_bodies: Vec<GpuBody> = bodies.iter().map(GpuBody::from).collect();
+        let data_bytes = bytemuck::cast_slice(&gpu_bodies);
         self.wgpu_state.queue.write_buffer(&self.bodies_buffer, 0, data_bytes);
 
         // update uniform buffer with metadata (in case num_bodies changed)
         self.update_uniforms_buffer();
     }
 
+    /// Sets the simulation container to be outlined.
+    pub fn set_boundary(&mut self, boundary: &simulation::Boundary) {
+        view::set_boundary(&mut self.uniforms, boundary);
+        self.update_uniforms_buffer();
+    }
+
     /// Resizes the renderer to the given width and height (in pixels) of the viewport.
     pub fn resize(&mut self, width: u32, height: u32) {
-        self.uniforms.view_port = [width, height];
-        let aspect = width as f32 / height as f32;
-        self.uniforms.cam_half_size[0] = self.uniforms.cam_half_size[1] * aspect;
+        self.camera.resize(width, height);
+        self.depth_view = create_depth_view(&self.wgpu_state.device, width, height);
 
         // reconfigure surface and update uniforms buffer
         self.wgpu_state.configure_surface(width, height);
-        self.update_uniforms_buffer();
+        self.update_camera();
     }
 
     /// Zooms the camera in or out, centered on the given pixel coordinates (in range [0, view_port.x/y]).
     pub fn zoom_camera(&mut self, px: f32, py: f32, zoom_factor: f32) {
-        let cam_half_size = &mut self.uniforms.cam_half_size;
-        let cam_center = &mut self.uniforms.cam_center;
-
-        let ndc_x = (px / self.uniforms.view_port[0] as f32) * 2.0 - 1.0;
-        let ndc_y = -((py / self.uniforms.view_port[1] as f32) * 2.0 - 1.0);
-
-        let world_x = cam_center[0] + ndc_x * cam_half_size[0];
-        let world_y = cam_center[1] + ndc_y * cam_half_size[1];
+        self.camera.zoom(px, py, zoom_factor);
+        self.update_camera();
+    }
 
-        cam_half_size[0] /= zoom_factor;
-        cam_half_size[1] /= zoom_factor;
+    /// Converts pixel coordinates (in range [0, view_port.x/y]) to world coordinates. In 3D, the
+    /// point under them on the plane through the camera's target.
+    pub fn screen_to_world(&self, px: f32, py: f32) -> simulation::Vector {
+        self.camera.screen_to_world(px, py)
+    }
 
-        cam_center[0] = world_x - ndc_x * cam_half_size[0];
-        cam_center[1] = world_y - ndc_y * cam_half_size[1];
-        self.update_uniforms_buffer();
+    /// Converts a length in pixels to world units.
+    pub fn screen_to_world_length(&self, length: f32) -> f32 {
+        self.camera.screen_to_world_length(length)
     }
 
     /// Pans the camera by the given delta in pixels.
     pub fn pan_camera(&mut self, delta_px: f32, delta_py: f32) {
-        let cam_half_size = &self.uniforms.cam_half_size;
-        let delta_x = (delta_px / self.uniforms.view_port[0] as f32) * 2.0 * cam_half_size[0];
-        let delta_y = -(delta_py / self.uniforms.view_port[1] as f32) * 2.0 * cam_half_size[1];
-        self.uniforms.cam_center[0] += delta_x;
-        self.uniforms.cam_center[1] += delta_y;
-        self.update_uniforms_buffer();
+        self.camera.pan(delta_px, delta_py);
+        self.update_camera();
+    }
+
+    /// Rotates the camera around its target by the given mouse movement in pixels.
+    #[cfg(feature = "dim3")]
+    pub fn orbit_camera(&mut self, delta_px: f32, delta_py: f32) {
+        self.camera.orbit(delta_px, delta_py);
+        self.update_camera();
+    }
+
+    /// Ray from the camera through the given pixel coordinates, as origin and unit direction.
+    #[cfg(feature = "dim3")]
+    pub fn screen_ray(&self, px: f32, py: f32) -> (simulation::Vector, simulation::Vector) {
+        self.camera.screen_ray(px, py)
+    }
+
+    /// Half-spaces bounding the part of the world seen through the given screen rectangle.
+    #[cfg(feature = "dim3")]
+    pub fn frustum(&self, px0: f32, py0: f32, px1: f32, py1: f32) -> [(simulation::Vector, simulation::Scalar); 5] {
+        self.camera.frustum(px0, py0, px1, py1)
     }
-}
\ No newline at end of file
+}
+
+/// Creates the depth buffer for a viewport of the given size, if the view uses one.
+fn create_depth_view(device: &wgpu::Device, width: u32, height: u32) -> Option<wgpu::TextureView> {
+    let format = view::DEPTH_FORMAT?;
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("depth texture"),
+        size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+}
@@ -3,7 +3,8 @@ struct Uniforms {
     cam_center: vec2<f32>,
     cam_half_size: vec2<f32>,
     num_bodies: u32,
-    _pad: u32,
+    boundary_kind: u32,
+    boundary: vec4<f32>,
 }
 
 struct Body {
@@ -11,6 +12,12 @@ struct Body {
     velocity: vec2f,
     mass: f32,
     radius: f32,
+    angle: f32,
+    angular_velocity: f32,
+    material: u32,
+    kind: u32,
+    id: u32,
+    _pad: u32,
 }
 
 struct VertexOutput {
@@ -18,6 +25,9 @@ struct VertexOutput {
     @location(0) world_position: vec2f,
     @location(1) body_center: vec2f,
     @location(2) body_radius: f32,
+    @location(3) body_angle: f32,
+    @location(4) brightness: f32,
+    @location(5) @interpolate(flat) body_kind: u32,
 }
 
 @group(0) @binding(0) var<uniform> uniforms: Uniforms;
@@ -28,7 +38,12 @@ fn vertex_main(
     @builtin(vertex_index) vertex_index: u32,
     @builtin(instance_index) instance_index: u32
 ) -> VertexOutput {
-    let body = bodies[instance_index];
+    // periodic domains draw every body in a 3x3 grid of tiles, the center tile being the domain
+    let body = bodies[instance_index % uniforms.num_bodies];
+    let tile = instance_index / uniforms.num_bodies;
+    let domain_size = uniforms.boundary.zw - uniforms.boundary.xy;
+    let tile_offset = vec2f(f32(tile % 3u) - 1.0, f32(tile / 3u) - 1.0) * domain_size;
+    let position = body.position + tile_offset;
     
     // create a quad from two triangles
     var quad_positions = array<vec2f, 6>(
@@ -41,7 +56,7 @@ fn vertex_main(
     );
     
     let quad_pos = quad_positions[vertex_index];
-    let world_pos = body.position + quad_pos * body.radius; // world pos of vertex
+    let world_pos = position + quad_pos * body.radius; // world pos of vertex
     
     // convert to ndc coords [-1, 1]
     let ndc = (world_pos - uniforms.cam_center) / uniforms.cam_half_size;
@@ -49,8 +64,11 @@ fn vertex_main(
     var output: VertexOutput;
     output.clip_position = vec4f(ndc, 0.0, 1.0);
     output.world_position = world_pos;
-    output.body_center = body.position;
+    output.body_center = position;
     output.body_radius = body.radius;
+    output.body_angle = body.angle;
+    output.body_kind = body.kind;
+    output.brightness = select(0.4, 1.0, tile == 4u || uniforms.boundary_kind != 3u); // dim ghost images
     
     return output;
 }
@@ -64,6 +82,66 @@ fn fragment_main(in: VertexOutput) -> @location(0) vec4f {
         discard;
     }
     
-    let color = vec3f(1.0, 1.0, 1.0);
-    return vec4f(color, 1.0);
+    // rotate into the body's frame and darken a radial marker line, so spin is visible
+    let c = cos(in.body_angle);
+    let s = sin(in.body_angle);
+    let local = vec2f(c * r.x + s * r.y, -s * r.x + c * r.y);
+    // static bodies are blue, kinematic bodies orange
+    var color = vec3f(1.0, 1.0, 1.0);
+    if (in.body_kind == 1u) {
+        color = vec3f(0.5, 0.7, 1.0);
+    } else if (in.body_kind == 2u) {
+        color = vec3f(1.0, 0.7, 0.4);
+    }
+    if (local.x > 0.0 && abs(local.y) < 0.15 * in.body_radius) {
+        color = vec3f(0.4, 0.4, 0.4);
+    }
+    return vec4f(color * in.brightness, 1.0);
+}
+
+struct BoundaryOutput {
+    @builtin(position) clip_position: vec4f,
+    @location(0) world_position: vec2f,
+}
+
+@vertex
+fn boundary_vertex_main(@builtin(vertex_index) vertex_index: u32) -> BoundaryOutput {
+    // full screen quad, the fragment shader draws the container outline
+    var quad_positions = array<vec2f, 6>(
+        vec2f(-1.0, -1.0),
+        vec2f( 1.0, -1.0),
+        vec2f(-1.0,  1.0),
+        vec2f(-1.0,  1.0),
+        vec2f( 1.0, -1.0),
+        vec2f( 1.0,  1.0),
+    );
+    let ndc = quad_positions[vertex_index];
+
+    var output: BoundaryOutput;
+    output.clip_position = vec4f(ndc, 0.0, 1.0);
+    output.world_position = uniforms.cam_center + ndc * uniforms.cam_half_size;
+    return output;
+}
+
+@fragment
+fn boundary_fragment_main(in: BoundaryOutput) -> @location(0) vec4f {
+    // signed distance to the container wall
+    var distance = 0.0;
+    if (uniforms.boundary_kind == 1u || uniforms.boundary_kind == 3u) {
+        let center = (uniforms.boundary.xy + uniforms.boundary.zw) * 0.5;
+        let half_size = (uniforms.boundary.zw - uniforms.boundary.xy) * 0.5;
+        let q = abs(in.world_position - center) - half_size;
+        distance = length(max(q, vec2f(0.0))) + min(max(q.x, q.y), 0.0);
+    } else if (uniforms.boundary_kind == 2u) {
+        distance = length(in.world_position - uniforms.boundary.xy) - uniforms.boundary.z;
+    } else {
+        discard;
+    }
+
+    // keep the outline about 1.5 pixels wide at any zoom level
+    let pixel_size = 2.0 * uniforms.cam_half_size.x / f32(uniforms.view_port.x);
+    if (abs(distance) > 1.5 * pixel_size) {
+        discard;
+    }
+    return vec4f(0.5, 0.5, 0.5, 1.0);
 }
\ No newline at end of file
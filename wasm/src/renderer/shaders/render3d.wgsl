@@ -0,0 +1,158 @@
+struct Uniforms {
+    view_projection: mat4x4f,
+    eye: vec4f,
+    up: vec4f,
+    num_bodies: u32,
+    boundary_kind: u32,
+    boundary: array<vec4f, 2>,
+}
+
+struct Body {
+    position: vec3f,
+    radius: f32,
+    velocity: vec3f,
+    mass: f32,
+    material: u32,
+    kind: u32,
+    id: u32,
+    _pad: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4f,
+    @location(0) world_position: vec3f,
+    @location(1) body_center: vec3f,
+    @location(2) body_radius: f32,
+    @location(3) @interpolate(flat) body_kind: u32,
+}
+
+struct FragmentOutput {
+    @location(0) color: vec4f,
+    @builtin(frag_depth) depth: f32,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+@group(0) @binding(1) var<storage, read> bodies: array<Body>;
+
+const CIRCLE_SEGMENTS: u32 = 64u;
+const TAU: f32 = 6.283185307;
+
+@vertex
+fn vertex_main(
+    @builtin(vertex_index) vertex_index: u32,
+    @builtin(instance_index) instance_index: u32
+) -> VertexOutput {
+    let body = bodies[instance_index];
+
+    // create a quad from two triangles
+    var quad_positions = array<vec2f, 6>(
+        vec2f(-1.0, -1.0),
+        vec2f( 1.0, -1.0),
+        vec2f(-1.0,  1.0),
+        vec2f(-1.0,  1.0),
+        vec2f( 1.0, -1.0),
+        vec2f( 1.0,  1.0),
+    );
+    let quad_pos = quad_positions[vertex_index];
+
+    var output: VertexOutput;
+    output.body_center = body.position;
+    output.body_radius = body.radius;
+    output.body_kind = body.kind;
+
+    // skip bodies the camera is inside of (all vertices collapse to a point outside the view)
+    let to_eye = uniforms.eye.xyz - body.position;
+    let distance = length(to_eye);
+    if (distance <= body.radius) {
+        output.clip_position = vec4f(2.0, 2.0, 0.0, 1.0);
+        output.world_position = body.position;
+        return output;
+    }
+
+    // quad facing the camera, large enough to cover the sphere's silhouette in perspective
+    let normal = to_eye / distance;
+    let right = normalize(cross(uniforms.up.xyz, normal));
+    let up = cross(normal, right);
+    let half_size = body.radius * distance / sqrt(distance * distance - body.radius * body.radius);
+    let world_pos = body.position + (right * quad_pos.x + up * quad_pos.y) * half_size;
+
+    output.clip_position = uniforms.view_projection * vec4f(world_pos, 1.0);
+    output.world_position = world_pos;
+    return output;
+}
+
+@fragment
+fn fragment_main(in: VertexOutput) -> FragmentOutput {
+    // intersect the ray from the camera through this fragment with the sphere
+    let eye = uniforms.eye.xyz;
+    let ray = normalize(in.world_position - eye);
+    let to_center = in.body_center - eye;
+    let along = dot(to_center, ray);
+    let discriminant = along * along - dot(to_center, to_center) + in.body_radius * in.body_radius;
+    if (discriminant < 0.0) {
+        discard;
+    }
+    let hit = eye + ray * (along - sqrt(discriminant));
+    let normal = (hit - in.body_center) / in.body_radius;
+
+    // static bodies are blue, kinematic bodies orange
+    var color = vec3f(1.0, 1.0, 1.0);
+    if (in.body_kind == 1u) {
+        color = vec3f(0.5, 0.7, 1.0);
+    } else if (in.body_kind == 2u) {
+        color = vec3f(1.0, 0.7, 0.4);
+    }
+
+    // diffuse light from above the camera, plus some ambient so the dark side stays visible
+    let light = normalize(uniforms.up.xyz - ray);
+    let diffuse = max(dot(normal, light), 0.0);
+
+    let clip = uniforms.view_projection * vec4f(hit, 1.0);
+    var output: FragmentOutput;
+    output.color = vec4f(color * (0.25 + 0.75 * diffuse), 1.0);
+    output.depth = clip.z / clip.w;
+    return output;
+}
+
+struct BoundaryOutput {
+    @builtin(position) clip_position: vec4f,
+}
+
+@vertex
+fn boundary_vertex_main(@builtin(vertex_index) vertex_index: u32) -> BoundaryOutput {
+    var position: vec3f;
+    if (uniforms.boundary_kind == 2u) {
+        // three great circles, in the xy, yz and zx planes, each split into line segments
+        let circle = vertex_index / (2u * CIRCLE_SEGMENTS);
+        let point = (vertex_index % (2u * CIRCLE_SEGMENTS) + 1u) / 2u; // segment i joins points i and i + 1
+        let angle = f32(point) * TAU / f32(CIRCLE_SEGMENTS);
+        let offset = vec3f(cos(angle), sin(angle), 0.0) * uniforms.boundary[0].w;
+        if (circle == 0u) {
+            position = offset;
+        } else if (circle == 1u) {
+            position = offset.zxy;
+        } else {
+            position = offset.yzx;
+        }
+        position += uniforms.boundary[0].xyz;
+    } else {
+        // the 12 box edges, as pairs of corners (bit k of a corner set for the max side on axis k)
+        var corners = array<u32, 24>(
+            0u, 1u, 2u, 3u, 4u, 5u, 6u, 7u,
+            0u, 2u, 1u, 3u, 4u, 6u, 5u, 7u,
+            0u, 4u, 1u, 5u, 2u, 6u, 3u, 7u,
+        );
+        let corner = corners[vertex_index];
+        let side = vec3f(f32(corner & 1u), f32((corner >> 1u) & 1u), f32((corner >> 2u) & 1u));
+        position = mix(uniforms.boundary[0].xyz, uniforms.boundary[1].xyz, side);
+    }
+
+    var output: BoundaryOutput;
+    output.clip_position = uniforms.view_projection * vec4f(position, 1.0);
+    return output;
+}
+
+@fragment
+fn boundary_fragment_main(in: BoundaryOutput) -> @location(0) vec4f {
+    return vec4f(0.5, 0.5, 0.5, 1.0);
+}
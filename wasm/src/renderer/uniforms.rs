@@ -1,6 +1,7 @@
 use bytemuck::{Pod, Zeroable};
 
 /// Uniforms struct for passing data to shaders.
+#[cfg(not(feature = "dim3"))]
 #[repr(C)]
 #[derive(Copy, Clone, Pod, Zeroable)]
 pub struct Uniforms {
@@ -8,5 +9,20 @@ pub struct Uniforms {
     pub cam_center: [f32; 2],
     pub cam_half_size: [f32; 2],
     pub num_bodies: u32,
-    pub _pad: u32,
-}
\ No newline at end of file
+    pub boundary_kind: u32, // 0 = open, 1 = box, 2 = circle, 3 = periodic
+    pub boundary: [f32; 4], // box/periodic: min.x, min.y, max.x, max.y, circle: center.x, center.y, radius, 0
+}
+
+/// Uniforms struct for passing data to the 3D shaders.
+#[cfg(feature = "dim3")]
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+pub struct Uniforms {
+    pub view_projection: [[f32; 4]; 4], // column-major
+    pub eye: [f32; 4],                  // camera position, w unused
+    pub up: [f32; 4],                   // camera up direction, w unused
+    pub num_bodies: u32,
+    pub boundary_kind: u32, // 0 = open, 1 = box, 2 = sphere, 3 = periodic
+    pub _pad: [u32; 2],     // boundary array must be 16-byte aligned
+    pub boundary: [[f32; 4]; 2], // box/periodic: min, max, sphere: center and radius (w of the first)
+}
@@ -0,0 +1,104 @@
+use crate::renderer::uniforms::Uniforms;
+use crate::simulation;
+
+/// Shader drawing bodies as flat discs, and the container outline over a full screen quad.
+pub const SHADER_CODE: &str = include_str!("shaders/render.wgsl");
+
+pub const BOUNDARY_TOPOLOGY: wgpu::PrimitiveTopology = wgpu::PrimitiveTopology::TriangleList;
+
+/// Bodies are drawn in order, without a depth buffer.
+pub const DEPTH_FORMAT: Option<wgpu::TextureFormat> = None;
+
+/// Orthographic camera looking down on the simulation plane.
+pub struct Camera {
+    view_port: [u32; 2],
+    center: [f32; 2],
+    half_size: [f32; 2],
+}
+
+impl Camera {
+    pub fn new(width: u32, height: u32) -> Self {
+        Camera {
+            view_port: [width, height],
+            center: [0.0, 0.0],
+            half_size: [10.0, 10.0],
+        }
+    }
+
+    /// Writes the camera into the shader uniforms.
+    pub fn apply(&self, uniforms: &mut Uniforms) {
+        uniforms.view_port = self.view_port;
+        uniforms.cam_center = self.center;
+        uniforms.cam_half_size = self.half_size;
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.view_port = [width, height];
+        let aspect = width as f32 / height as f32;
+        self.half_size[0] = self.half_size[1] * aspect;
+    }
+
+    /// Zooms in or out, centered on the given pixel coordinates (in range [0, view_port.x/y]).
+    pub fn zoom(&mut self, px: f32, py: f32, zoom_factor: f32) {
+        let (ndc_x, ndc_y) = self.ndc(px, py);
+
+        let world_x = self.center[0] + ndc_x * self.half_size[0];
+        let world_y = self.center[1] + ndc_y * self.half_size[1];
+
+        self.half_size[0] /= zoom_factor;
+        self.half_size[1] /= zoom_factor;
+
+        self.center[0] = world_x - ndc_x * self.half_size[0];
+        self.center[1] = world_y - ndc_y * self.half_size[1];
+    }
+
+    /// Pans by the given delta in pixels.
+    pub fn pan(&mut self, delta_px: f32, delta_py: f32) {
+        let delta_x = (delta_px / self.view_port[0] as f32) * 2.0 * self.half_size[0];
+        let delta_y = -(delta_py / self.view_port[1] as f32) * 2.0 * self.half_size[1];
+        self.center[0] += delta_x;
+        self.center[1] += delta_y;
+    }
+
+    /// Converts pixel coordinates (in range [0, view_port.x/y]) to world coordinates.
+    pub fn screen_to_world(&self, px: f32, py: f32) -> simulation::Vector {
+        let (ndc_x, ndc_y) = self.ndc(px, py);
+        let world_x = self.center[0] + ndc_x * self.half_size[0];
+        let world_y = self.center[1] + ndc_y * self.half_size[1];
+        simulation::Vector::new(world_x as simulation::Scalar, world_y as simulation::Scalar)
+    }
+
+    /// Converts a length in pixels to world units.
+    pub fn screen_to_world_length(&self, length: f32) -> f32 {
+        length / self.view_port[1] as f32 * 2.0 * self.half_size[1]
+    }
+
+    fn ndc(&self, px: f32, py: f32) -> (f32, f32) {
+        let ndc_x = (px / self.view_port[0] as f32) * 2.0 - 1.0;
+        let ndc_y = -((py / self.view_port[1] as f32) * 2.0 - 1.0);
+        (ndc_x, ndc_y)
+    }
+}
+
+/// Writes the container to be outlined into the shader uniforms.
+#[allow(clippy::unnecessary_cast)] // `Scalar` is already f32 without the f64 feature
+pub fn set_boundary(uniforms: &mut Uniforms, boundary: &simulation::Boundary) {
+    let (kind, params) = match *boundary {
+        simulation::Boundary::Open => (0, [0.0; 4]),
+        simulation::Boundary::Box { min, max, .. } => (1, [min.x, min.y, max.x, max.y]),
+        simulation::Boundary::Circle { center, radius, .. } => (2, [center.x, center.y, radius, 0.0]),
+        simulation::Boundary::Periodic { min, max } => (3, [min.x, min.y, max.x, max.y]),
+    };
+    uniforms.boundary_kind = kind;
+    uniforms.boundary = params.map(|v| v as f32);
+}
+
+/// Number of vertices of the container outline: a full screen quad, or nothing in open space.
+pub fn boundary_vertices(boundary_kind: u32) -> u32 {
+    if boundary_kind != 0 { 6 } else { 0 }
+}
+
+/// Number of images drawn of every body: periodic domains also draw the 8 neighbouring images.
+pub fn tiles(boundary_kind: u32) -> u32 {
+    if boundary_kind == 3 { 9 } else { 1 }
+}
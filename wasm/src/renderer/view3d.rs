@@ -0,0 +1,184 @@
+use crate::renderer::uniforms::Uniforms;
+use crate::simulation::{self, Scalar, Vector};
+
+/// Shader drawing bodies as ray-traced impostor spheres, and the container outline as lines.
+pub const SHADER_CODE: &str = include_str!("shaders/render3d.wgsl");
+
+pub const BOUNDARY_TOPOLOGY: wgpu::PrimitiveTopology = wgpu::PrimitiveTopology::LineList;
+
+/// Reversed depth (1 at the near plane, 0 at infinity), cleared to 0 and tested with `Greater`.
+pub const DEPTH_FORMAT: Option<wgpu::TextureFormat> = Some(wgpu::TextureFormat::Depth32Float);
+
+/// Vertical field of view, in radians.
+const FOV_Y: Scalar = std::f64::consts::FRAC_PI_4 as Scalar;
+/// Segments per great circle of the spherical container outline, as in the shader.
+const CIRCLE_SEGMENTS: u32 = 64;
+/// Radians of orbit per pixel dragged.
+const ORBIT_SPEED: Scalar = 0.005;
+/// Pitch stays short of straight up or down, where the camera's right direction is undefined.
+const MAX_PITCH: Scalar = 1.55;
+
+/// Perspective camera orbiting a target point. At zero yaw and pitch it looks down the z axis
+/// with y up, the same view as the 2D camera.
+pub struct Camera {
+    view_port: [u32; 2],
+    target: Vector,
+    distance: Scalar,
+    yaw: Scalar,
+    pitch: Scalar,
+}
+
+#[allow(clippy::unnecessary_cast)] // `Scalar` is already f32 without the f64 feature
+impl Camera {
+    pub fn new(width: u32, height: u32) -> Self {
+        Camera {
+            view_port: [width, height],
+            target: Vector::zero(),
+            // same initial view height at the target as the 2D camera
+            distance: 10.0 / (FOV_Y / 2.0).tan(),
+            yaw: 0.0,
+            pitch: 0.0,
+        }
+    }
+
+    /// Camera position.
+    pub fn eye(&self) -> Vector {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        self.target + Vector::new(cos_pitch * sin_yaw, sin_pitch, cos_pitch * cos_yaw) * self.distance
+    }
+
+    /// Forward, right and up unit directions of the camera.
+    fn basis(&self) -> (Vector, Vector, Vector) {
+        let forward = (self.target - self.eye()).normalize();
+        let right = forward.cross(Vector::unit(1)).normalize();
+        (forward, right, right.cross(forward))
+    }
+
+    /// Writes the camera into the shader uniforms.
+    pub fn apply(&self, uniforms: &mut Uniforms) {
+        let eye = self.eye();
+        let (forward, right, up) = self.basis();
+        let focal = 1.0 / (FOV_Y / 2.0).tan();
+        let aspect = self.view_port[0] as Scalar / self.view_port[1] as Scalar;
+        let near = self.distance * 1e-3;
+
+        // perspective projection with an infinite far plane and reversed depth: clip w is the
+        // distance along the view direction and clip z the constant `near`
+        let rows = [right * (focal / aspect), up * focal, Vector::zero(), forward];
+        let offsets = [-(focal / aspect) * right.dot(eye), -focal * up.dot(eye), near, -forward.dot(eye)];
+        let mut view_projection = [[0.0; 4]; 4];
+        for (row, (axis, offset)) in rows.iter().zip(offsets).enumerate() {
+            for (column, value) in axis.axes().into_iter().enumerate() {
+                view_projection[column][row] = value as f32;
+            }
+            view_projection[3][row] = offset as f32;
+        }
+
+        uniforms.view_projection = view_projection;
+        uniforms.eye = [eye.x as f32, eye.y as f32, eye.z as f32, 0.0];
+        uniforms.up = [up.x as f32, up.y as f32, up.z as f32, 0.0];
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.view_port = [width, height];
+    }
+
+    /// Moves towards (or away from) the point under the given pixel coordinates, so it stays
+    /// under the cursor.
+    pub fn zoom(&mut self, px: f32, py: f32, zoom_factor: f32) {
+        let point = self.screen_to_world(px, py);
+        let zoom_factor = zoom_factor as Scalar;
+        self.target = point + (self.target - point) / zoom_factor;
+        self.distance /= zoom_factor;
+    }
+
+    /// Moves the target in the view plane by the given delta in pixels.
+    pub fn pan(&mut self, delta_px: f32, delta_py: f32) {
+        let (_, right, up) = self.basis();
+        let scale = self.world_per_pixel();
+        self.target += right * (delta_px as Scalar * scale) - up * (delta_py as Scalar * scale);
+    }
+
+    /// Rotates around the target by the given mouse movement in pixels.
+    pub fn orbit(&mut self, delta_px: f32, delta_py: f32) {
+        self.yaw -= delta_px as Scalar * ORBIT_SPEED;
+        self.pitch = (self.pitch + delta_py as Scalar * ORBIT_SPEED).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Ray from the camera through the given pixel coordinates, as origin and unit direction.
+    pub fn screen_ray(&self, px: f32, py: f32) -> (Vector, Vector) {
+        let (forward, right, up) = self.basis();
+        let half_height = (FOV_Y / 2.0).tan();
+        let half_width = half_height * self.view_port[0] as Scalar / self.view_port[1] as Scalar;
+        let ndc_x = (px / self.view_port[0] as f32) as Scalar * 2.0 - 1.0;
+        let ndc_y = -((py / self.view_port[1] as f32) as Scalar * 2.0 - 1.0);
+        let direction = forward + right * (ndc_x * half_width) + up * (ndc_y * half_height);
+        (self.eye(), direction.normalize())
+    }
+
+    /// Converts pixel coordinates to the world point under them on the plane through the target,
+    /// facing the camera.
+    pub fn screen_to_world(&self, px: f32, py: f32) -> Vector {
+        let (origin, direction) = self.screen_ray(px, py);
+        let (forward, _, _) = self.basis();
+        origin + direction * (self.distance / direction.dot(forward))
+    }
+
+    /// Half-spaces `normal · p >= offset` whose intersection is the pyramid from the camera through
+    /// the screen rectangle between the given pixel corners (in any order).
+    pub fn frustum(&self, px0: f32, py0: f32, px1: f32, py1: f32) -> [(Vector, Scalar); 5] {
+        let (forward, right, up) = self.basis();
+        let half_height = (FOV_Y / 2.0).tan();
+        let half_width = half_height * self.view_port[0] as Scalar / self.view_port[1] as Scalar;
+        let ndc_x = |px: f32| (px / self.view_port[0] as f32) as Scalar * 2.0 - 1.0;
+        let ndc_y = |py: f32| -((py / self.view_port[1] as f32) as Scalar * 2.0 - 1.0);
+        let (left, right_edge) = (ndc_x(px0.min(px1)) * half_width, ndc_x(px0.max(px1)) * half_width);
+        let (top, bottom) = (ndc_y(py0.min(py1)) * half_height, ndc_y(py0.max(py1)) * half_height);
+
+        // a point at `depth` along the view direction is inside if its offsets along the right and
+        // up directions are within the edges scaled by the depth
+        let eye = self.eye();
+        [forward, right - forward * left, forward * right_edge - right, forward * top - up, up - forward * bottom]
+            .map(|normal| (normal, normal.dot(eye)))
+    }
+
+    /// Converts a length in pixels to world units, at the target's distance.
+    pub fn screen_to_world_length(&self, length: f32) -> f32 {
+        (length as Scalar * self.world_per_pixel()) as f32
+    }
+
+    fn world_per_pixel(&self) -> Scalar {
+        2.0 * self.distance * (FOV_Y / 2.0).tan() / self.view_port[1] as Scalar
+    }
+}
+
+/// Writes the container to be outlined into the shader uniforms.
+#[allow(clippy::unnecessary_cast)] // `Scalar` is already f32 without the f64 feature
+pub fn set_boundary(uniforms: &mut Uniforms, boundary: &simulation::Boundary) {
+    let point = |v: Vector, w: Scalar| [v.x as f32, v.y as f32, v.z as f32, w as f32];
+    let (kind, params) = match *boundary {
+        simulation::Boundary::Open => (0, [[0.0; 4]; 2]),
+        simulation::Boundary::Box { min, max, .. } => (1, [point(min, 0.0), point(max, 0.0)]),
+        simulation::Boundary::Circle { center, radius, .. } => (2, [point(center, radius), [0.0; 4]]),
+        simulation::Boundary::Periodic { min, max } => (3, [point(min, 0.0), point(max, 0.0)]),
+    };
+    uniforms.boundary_kind = kind;
+    uniforms.boundary = params;
+}
+
+/// Number of vertices of the container outline, drawn as a line list: the 12 edges of a box, or
+/// 3 great circles of a sphere.
+pub fn boundary_vertices(boundary_kind: u32) -> u32 {
+    match boundary_kind {
+        1 | 3 => 24,
+        2 => 3 * CIRCLE_SEGMENTS * 2,
+        _ => 0,
+    }
+}
+
+/// Number of images drawn of every body. Periodic images aren't drawn in 3D, they would hide the
+/// domain behind 26 copies of itself.
+pub fn tiles(_boundary_kind: u32) -> u32 {
+    1
+}
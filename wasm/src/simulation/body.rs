@@ -1,23 +1,119 @@
-use bytemuck::{Pod, Zeroable};
+use serde::Serialize;
 
-use crate::simulation::vec2::Vec2;
+use crate::simulation::config::{self, ConfigError};
+use crate::simulation::vec2::{Scalar, Spin, Vector};
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+/// How a body responds to forces and collisions. Uploaded to the GPU as its discriminant.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum BodyKind {
+    /// Moved by gravity, force fields and collisions.
+    Dynamic = 0,
+    /// Never moves. Still attracts other bodies, and collides as if its mass were infinite.
+    Static = 1,
+    /// Moves with its own velocity, set by the user to follow a scripted path. Unaffected by forces,
+    /// and collides as if its mass were infinite.
+    Kinematic = 2,
+}
+
+/// Stable identifier of a body in a simulation, unaffected by other bodies being added, removed
+/// or merged. 0 until the body is added to a simulation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct BodyId(pub u32);
+
+#[derive(Clone, Copy, Debug, Serialize)]
 pub struct Body {
-    pub position: Vec2,
-    pub velocity: Vec2,
-    pub mass: f32,
-    pub radius: f32,
+    pub position: Vector,
+    pub velocity: Vector,
+    pub mass: Scalar,
+    pub radius: Scalar,
+    /// Electric charge, felt through the Coulomb and Yukawa force laws.
+    pub charge: Scalar,
+    /// Rotation about the z axis. 3D bodies don't track their orientation, spheres look the same
+    /// from every side.
+    #[cfg(not(feature = "dim3"))]
+    pub angle: Scalar,
+    pub angular_velocity: Spin,
+    /// Index of the body's material in the simulation's material list.
+    pub material: u32,
+    /// Change with `set_kind`, which stops static bodies.
+    pub kind: BodyKind,
+    pub id: BodyId,
 }
 
 impl Body {
-    pub fn new(position: Vec2, velocity: Vec2, mass: f32, radius: f32) -> Self {
+    pub fn new(position: Vector, velocity: Vector, mass: Scalar, radius: Scalar) -> Self {
         Body {
             position,
             velocity,
             mass,
             radius,
+            charge: 0.0,
+            #[cfg(not(feature = "dim3"))]
+            angle: 0.0,
+            angular_velocity: Spin::default(),
+            material: 0,
+            kind: BodyKind::Dynamic,
+            id: BodyId(0),
+        }
+    }
+
+    /// Changes how the body moves. Static bodies are stopped.
+    pub fn set_kind(&mut self, kind: BodyKind) {
+        self.kind = kind;
+        if kind == BodyKind::Static {
+            self.velocity = Vector::zero();
+            self.angular_velocity = Spin::default();
         }
     }
-}
\ No newline at end of file
+
+    /// Returns whether the body is moved by forces and collisions.
+    pub fn is_dynamic(&self) -> bool {
+        self.kind == BodyKind::Dynamic
+    }
+
+    /// Checks that the body has a finite state, and non-negative mass and radius. Massless and
+    /// point bodies are allowed.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        config::check(config::finite(self.position), "body.position", "must be finite")?;
+        config::check(config::finite(self.velocity), "body.velocity", "must be finite")?;
+        config::check(config::non_negative(self.mass), "body.mass", "must be finite and non-negative")?;
+        config::check(config::non_negative(self.radius), "body.radius", "must be finite and non-negative")?;
+        config::check(self.charge.is_finite(), "body.charge", "must be finite")
+    }
+
+    /// 1 / mass, or 0 for static and kinematic bodies (infinite mass in collisions).
+    pub fn inverse_mass(&self) -> Scalar {
+        if self.is_dynamic() && self.mass > 0.0 { 1.0 / self.mass } else { 0.0 }
+    }
+
+    /// 1 / moment of inertia, or 0 for static and kinematic bodies and point bodies.
+    pub fn inverse_inertia(&self) -> Scalar {
+        let inertia = self.moment_of_inertia();
+        if self.is_dynamic() && inertia > 0.0 { 1.0 / inertia } else { 0.0 }
+    }
+
+    /// Moment of inertia about the body's center, treating it as a uniform solid sphere.
+    pub fn moment_of_inertia(&self) -> Scalar {
+        0.4 * self.mass * self.radius * self.radius
+    }
+
+    /// Spin angular momentum about the body's center.
+    pub fn spin_angular_momentum(&self) -> Spin {
+        self.angular_velocity * self.moment_of_inertia()
+    }
+
+    /// Kinetic energy of the body's spin.
+    pub fn rotational_energy(&self) -> Scalar {
+        #[cfg(not(feature = "dim3"))]
+        let spin_sq = self.angular_velocity * self.angular_velocity;
+        #[cfg(feature = "dim3")]
+        let spin_sq = self.angular_velocity.length_squared();
+        0.5 * self.moment_of_inertia() * spin_sq
+    }
+
+    /// Returns whether this body overlaps `other`.
+    pub fn overlaps(&self, other: &Body) -> bool {
+        let min_distance = self.radius + other.radius;
+        (other.position - self.position).length_squared() < min_distance * min_distance
+    }
+}
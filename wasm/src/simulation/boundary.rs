@@ -0,0 +1,133 @@
+use serde::Deserialize;
+
+use crate::simulation::body::Body;
+use crate::simulation::config::{self, ConfigError};
+use crate::simulation::vec2::{Scalar, Vector};
+
+/// The domain bodies live in.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+pub enum Boundary {
+    /// Unbounded space.
+    Open,
+    /// Axis-aligned box container with walls at `min` and `max`, made of material `material`.
+    Box {
+        min: Vector,
+        max: Vector,
+        #[serde(default)]
+        material: u32,
+    },
+    /// Circular (spherical in 3D) container, made of material `material`.
+    Circle {
+        center: Vector,
+        radius: Scalar,
+        #[serde(default)]
+        material: u32,
+    },
+    /// Periodic (toroidal) domain spanning `min` to `max`: bodies leaving one side re-enter on the
+    /// opposite side, and all interactions use the nearest periodic image (gravity from farther
+    /// images is ignored rather than Ewald summed).
+    Periodic { min: Vector, max: Vector },
+}
+
+/// A contact between a body and a container wall.
+#[derive(Clone, Copy, Debug)]
+pub struct WallContact {
+    /// Identifies the wall, so contacts can be matched between steps.
+    pub wall: usize,
+    /// Unit normal pointing from the body into the wall.
+    pub normal: Vector,
+    pub penetration: Scalar,
+}
+
+impl Boundary {
+    /// Checks that the container has a finite, non-empty extent.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        match *self {
+            Boundary::Open => Ok(()),
+            Boundary::Box { min, max, .. } | Boundary::Periodic { min, max } => {
+                let below = min.axes().into_iter().zip(max.axes()).all(|(lo, hi)| lo.is_finite() && hi.is_finite() && lo < hi);
+                config::check(below, "boundary", "min must be below max on every axis")
+            }
+            Boundary::Circle { center, radius, .. } => {
+                config::check(config::finite(center), "boundary.center", "must be finite")?;
+                config::check(config::positive(radius), "boundary.radius", "must be positive")
+            }
+        }
+    }
+
+    /// Returns the contacts between `body` and the container walls (at most one per wall).
+    pub fn wall_contacts(&self, body: &Body) -> Vec<WallContact> {
+        let mut contacts = vec![];
+        match *self {
+            Boundary::Open | Boundary::Periodic { .. } => {}
+            Boundary::Box { min, max, .. } => {
+                let (p, min, max) = (body.position.axes(), min.axes(), max.axes());
+                let r = body.radius;
+                // two walls per axis, the lower one first
+                for axis in 0..Vector::DIM {
+                    let walls = [
+                        (Vector::unit(axis) * -1.0, min[axis] - (p[axis] - r)),
+                        (Vector::unit(axis), (p[axis] + r) - max[axis]),
+                    ];
+                    for (side, (normal, penetration)) in walls.into_iter().enumerate() {
+                        if penetration > 0.0 {
+                            contacts.push(WallContact { wall: 2 * axis + side, normal, penetration });
+                        }
+                    }
+                }
+            }
+            Boundary::Circle { center, radius, .. } => {
+                let offset = body.position - center;
+                let distance = offset.length();
+                let penetration = distance + body.radius - radius;
+                if penetration > 0.0 {
+                    // arbitrary normal for a body exactly at the center of a too small container
+                    let normal = if distance > 0.0 { offset / distance } else { Vector::unit(0) };
+                    contacts.push(WallContact { wall: 0, normal, penetration });
+                }
+            }
+        }
+        contacts
+    }
+
+    /// Shortest displacement from `from` to `to`, i.e. to the nearest periodic image of `to` in a
+    /// periodic domain (minimum image convention).
+    pub fn displacement(&self, from: Vector, to: Vector) -> Vector {
+        let d = to - from;
+        match *self {
+            Boundary::Periodic { min, max } => {
+                let (d, size) = (d.axes(), (max - min).axes());
+                Vector::from_axes(std::array::from_fn(|k| d[k] - size[k] * (d[k] / size[k]).round()))
+            }
+            _ => d,
+        }
+    }
+
+    /// Copy of `body` moved to its periodic image nearest to `position`.
+    pub fn nearest_image(&self, body: &Body, position: Vector) -> Body {
+        Body {
+            position: position + self.displacement(position, body.position),
+            ..*body
+        }
+    }
+
+    /// Wraps `position` back into a periodic domain. Other boundaries leave it unchanged.
+    pub fn wrap(&self, position: Vector) -> Vector {
+        match *self {
+            Boundary::Periodic { min, max } => {
+                let (position, min, size) = (position.axes(), min.axes(), (max - min).axes());
+                Vector::from_axes(std::array::from_fn(|k| min[k] + (position[k] - min[k]).rem_euclid(size[k])))
+            }
+            _ => position,
+        }
+    }
+
+    /// Material of the container walls, if any.
+    pub fn material(&self) -> Option<u32> {
+        match *self {
+            Boundary::Open | Boundary::Periodic { .. } => None,
+            Boundary::Box { material, .. } | Boundary::Circle { material, .. } => Some(material),
+        }
+    }
+}
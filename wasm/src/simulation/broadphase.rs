@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::simulation::body::Body;
+use crate::simulation::boundary::Boundary;
+use crate::simulation::parallel;
+use crate::simulation::vec2::{Scalar, Vector};
+
+/// Integer coordinates of a grid cell. Wide enough that far-away bodies (whose coordinates
+/// saturate) don't overflow when stepping to neighbouring cells.
+type Cell = [i64; Vector::DIM];
+
+/// Algorithm used to find candidate pairs for the collision narrowphase.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum Broadphase {
+    /// Tests every pair of bodies, O(n²).
+    BruteForce,
+    /// Buckets bodies into a uniform grid with cells as wide as the largest body, so only bodies
+    /// in neighbouring cells are paired.
+    SpatialHash,
+}
+
+/// Returns the candidate collision pairs `(i, j)` with `i < j`, sorted in the same order as the
+/// brute-force pass so the narrowphase resolves them identically. In a periodic domain, pairs
+/// touching across the domain edges are included.
+pub fn candidate_pairs(bodies: &[Body], broadphase: Broadphase, boundary: &Boundary) -> Vec<(usize, usize)> {
+    match broadphase {
+        Broadphase::BruteForce => {
+            let mut pairs = vec![];
+            for i in 0..bodies.len() {
+                for j in i + 1..bodies.len() {
+                    pairs.push((i, j));
+                }
+            }
+            pairs
+        }
+        Broadphase::SpatialHash => spatial_hash_pairs(bodies, boundary),
+    }
+}
+
+fn spatial_hash_pairs(bodies: &[Body], boundary: &Boundary) -> Vec<(usize, usize)> {
+    // two bodies can only touch if they are closer than the largest diameter
+    let max_radius = bodies.iter().map(|b| b.radius).fold(0.0, Scalar::max);
+    if max_radius <= 0.0 {
+        return vec![];
+    }
+
+    // a periodic domain is tiled by a whole number of cells per axis, so cell indices can wrap
+    let (origin, cell_size, wrap) = match *boundary {
+        Boundary::Periodic { min, max } => {
+            let size = (max - min).axes();
+            let cells = size.map(|size| (size / (2.0 * max_radius)).floor().max(1.0));
+            let cell_size = std::array::from_fn(|k| size[k] / cells[k]);
+            (min, cell_size, Some(cells.map(|cells| cells as i64)))
+        }
+        _ => (Vector::zero(), [2.0 * max_radius; Vector::DIM], None),
+    };
+    let wrap_cell = |cell: Cell| match wrap {
+        Some(cells) => std::array::from_fn(|k| cell[k].rem_euclid(cells[k])),
+        None => cell,
+    };
+    let cell_of = |body: &Body| {
+        let (position, origin) = (body.position.axes(), origin.axes());
+        wrap_cell(std::array::from_fn(|k| ((position[k] - origin[k]) / cell_size[k]).floor() as i64))
+    };
+
+    let mut grid: HashMap<Cell, Vec<usize>> = HashMap::new();
+    for (i, body) in bodies.iter().enumerate() {
+        grid.entry(cell_of(body)).or_default().push(i);
+    }
+
+    // neighbours of each body are gathered in parallel (with the `parallel` feature), the sort
+    // below makes the order independent of the thread count
+    let neighbours = parallel::map_indices(bodies.len(), |i| {
+        let cell = cell_of(&bodies[i]);
+        let mut pairs = vec![];
+        // the 3x3 (3x3x3 in 3D) block of cells around the body's cell
+        for neighbour in 0..3i64.pow(Vector::DIM as u32) {
+            let offset: Cell = std::array::from_fn(|k| neighbour / 3i64.pow(k as u32) % 3 - 1);
+            if let Some(cell) = grid.get(&wrap_cell(std::array::from_fn(|k| cell[k].saturating_add(offset[k])))) {
+                pairs.extend(cell.iter().filter(|&&j| j > i).map(|&j| (i, j)));
+            }
+        }
+        pairs
+    });
+    let mut pairs: Vec<(usize, usize)> = neighbours.into_iter().flatten().collect();
+    pairs.sort_unstable();
+    // small periodic grids can reach the same neighbouring cell twice
+    pairs.dedup();
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    /// Bodies of mixed sizes scattered over (and slightly past) the square of side 40 centered on
+    /// the origin.
+    fn scattered_bodies() -> Vec<Body> {
+        let mut rng = StdRng::seed_from_u64(3);
+        (0..300)
+            .map(|_| {
+                let position = Vector::from_axes(std::array::from_fn(|_| rng.random_range(-21.0..21.0)));
+                Body::new(position, Vector::zero(), 1.0, rng.random_range(0.2..2.0))
+            })
+            .collect()
+    }
+
+    fn overlapping(pairs: Vec<(usize, usize)>, bodies: &[Body], boundary: &Boundary) -> Vec<(usize, usize)> {
+        pairs
+            .into_iter()
+            .filter(|&(i, j)| bodies[i].overlaps(&boundary.nearest_image(&bodies[j], bodies[i].position)))
+            .collect()
+    }
+
+    fn assert_same_overlaps(bodies: &[Body], boundary: &Boundary) {
+        let brute_force = candidate_pairs(bodies, Broadphase::BruteForce, boundary);
+        let spatial_hash = candidate_pairs(bodies, Broadphase::SpatialHash, boundary);
+        assert!(spatial_hash.is_sorted());
+        let expected = overlapping(brute_force, bodies, boundary);
+        assert!(!expected.is_empty());
+        assert_eq!(overlapping(spatial_hash, bodies, boundary), expected);
+    }
+
+    #[test]
+    fn spatial_hash_finds_the_brute_force_overlaps() {
+        assert_same_overlaps(&scattered_bodies(), &Boundary::Open);
+    }
+
+    #[test]
+    fn spatial_hash_handles_far_away_bodies() {
+        let mut bodies = scattered_bodies();
+        for sign in [1.0, -1.0] {
+            bodies.push(Body::new(Vector::splat(sign * 1e10), Vector::zero(), 1.0, 1.0));
+            bodies.push(Body::new(Vector::splat(sign * 1e10), Vector::zero(), 1.0, 1.0));
+        }
+        assert_same_overlaps(&bodies, &Boundary::Open);
+    }
+
+    #[test]
+    fn spatial_hash_finds_the_brute_force_overlaps_across_periodic_edges() {
+        let boundary = Boundary::Periodic { min: Vector::splat(-20.0), max: Vector::splat(20.0) };
+        let bodies: Vec<Body> = scattered_bodies()
+            .into_iter()
+            .map(|mut body| {
+                body.position = boundary.wrap(body.position);
+                body
+            })
+            .collect();
+        assert_same_overlaps(&bodies, &boundary);
+
+        let across_edges = candidate_pairs(&bodies, Broadphase::BruteForce, &boundary)
+            .into_iter()
+            .filter(|&(i, j)| {
+                let (a, b) = (&bodies[i], &bodies[j]);
+                !a.overlaps(b) && a.overlaps(&boundary.nearest_image(b, a.position))
+            })
+            .count();
+        assert!(across_edges > 0);
+    }
+}
@@ -0,0 +1,59 @@
+use crate::simulation::body::Body;
+use crate::simulation::boundary::Boundary;
+use crate::simulation::broadphase::{self, Broadphase};
+use crate::simulation::vec2::{Scalar, Vector};
+
+/// A contact found by sweeping two bodies along their motion during a step.
+#[derive(Clone, Copy, Debug)]
+pub struct SweptContact {
+    /// Fraction of the step (in [0, 1]) at which the bodies first touch.
+    pub time_of_impact: Scalar,
+    pub i: usize,
+    pub j: usize,
+}
+
+/// Finds every pair of bodies that comes into contact while moving in a straight line from
+/// `start` to their current positions, and wasn't already overlapping at the start.
+pub fn swept_contacts(start: &[Vector], bodies: &[Body], broadphase: Broadphase, boundary: &Boundary) -> Vec<SweptContact> {
+    // proxies covering the whole swept path of each body
+    let proxies: Vec<Body> = bodies
+        .iter()
+        .zip(start)
+        .map(|(body, &p0)| {
+            let half_path = (body.position - p0) / 2.0;
+            Body::new(p0 + half_path, body.velocity, body.mass, body.radius + half_path.length())
+        })
+        .collect();
+
+    broadphase::candidate_pairs(&proxies, broadphase, boundary)
+        .into_iter()
+        .filter_map(|(i, j)| {
+            let radius_sum = bodies[i].radius + bodies[j].radius;
+            // sweep the periodic image of j nearest to i
+            let b0 = start[i] + boundary.displacement(start[i], start[j]);
+            let b1 = b0 + (bodies[j].position - start[j]);
+            let toi = time_of_impact(start[i], bodies[i].position, b0, b1, radius_sum)?;
+            Some(SweptContact { time_of_impact: toi, i, j })
+        })
+        .collect()
+}
+
+/// Returns the fraction of the step at which two circles moving linearly from `a0` to `a1` and
+/// from `b0` to `b1` first come within `radius_sum` of each other, if they do during the step.
+pub fn time_of_impact(a0: Vector, a1: Vector, b0: Vector, b1: Vector, radius_sum: Scalar) -> Option<Scalar> {
+    // solve |d0 + dd * t|² = radius_sum² for the earliest t
+    let d0 = b0 - a0;
+    let dd = (b1 - a1) - d0;
+    let a = dd.length_squared();
+    let b = 2.0 * d0.dot(dd);
+    let c = d0.length_squared() - radius_sum * radius_sum;
+    if c <= 0.0 || a == 0.0 {
+        return None; // already overlapping (left to the discrete pass) or no relative motion
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let t = (-b - discriminant.sqrt()) / (2.0 * a);
+    (0.0..=1.0).contains(&t).then_some(t)
+}
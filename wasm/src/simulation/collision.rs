@@ -0,0 +1,225 @@
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::simulation::body::Body;
+use crate::simulation::config::{self, ConfigError};
+use crate::simulation::vec2::consts::PI;
+use crate::simulation::vec2::{Scalar, Spin, Vector};
+
+/// How overlapping bodies are resolved.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+pub enum CollisionMode {
+    /// Bodies bounce apart with an impulse scaled by the coefficient of restitution.
+    Bounce,
+    /// Bodies merge into one (perfect accretion), conserving mass and momentum.
+    Merge,
+    /// Bodies shatter into fragments when the impact energy is high enough, and merge otherwise.
+    Fragment(Fragmentation),
+}
+
+impl CollisionMode {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        match *self {
+            CollisionMode::Bounce | CollisionMode::Merge => Ok(()),
+            CollisionMode::Fragment(params) => params.validate(),
+        }
+    }
+}
+
+/// Upper bound of `Fragmentation::max_fragments`, so a single collision can't flood the simulation
+/// with bodies.
+pub const MAX_FRAGMENTS: u32 = 64;
+
+/// Parameters of the fragmentation model.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Fragmentation {
+    /// Specific impact energy (center-of-mass frame kinetic energy per unit of total mass) above
+    /// which colliding bodies shatter.
+    pub energy_threshold: Scalar,
+    /// Maximum number of fragments produced by a shattering collision, between 2 and
+    /// `MAX_FRAGMENTS`.
+    pub max_fragments: u32,
+    /// Exponent of the fragment mass distribution: the k-th largest fragment has mass proportional
+    /// to k^-exponent, so larger values give one dominant remnant and fine debris.
+    pub mass_exponent: Scalar,
+    /// Fraction of the impact energy converted into fragment ejection velocity (the rest is lost).
+    pub ejection_efficiency: Scalar,
+    /// Fragments are never lighter than this; fewer fragments are produced instead.
+    pub min_fragment_mass: Scalar,
+}
+
+impl Default for Fragmentation {
+    fn default() -> Self {
+        Fragmentation {
+            energy_threshold: 2000.0,
+            max_fragments: 6,
+            mass_exponent: 1.0,
+            ejection_efficiency: 0.3,
+            min_fragment_mass: 0.5,
+        }
+    }
+}
+
+impl Fragmentation {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        config::check(config::non_negative(self.energy_threshold), "collision_mode.energy_threshold", "must be non-negative")?;
+        let max_fragments = (2..=MAX_FRAGMENTS).contains(&self.max_fragments);
+        config::check(max_fragments, "collision_mode.max_fragments", "must be between 2 and 64")?;
+        config::check(config::non_negative(self.mass_exponent), "collision_mode.mass_exponent", "must be non-negative")?;
+        let efficiency = config::fraction(self.ejection_efficiency);
+        config::check(efficiency, "collision_mode.ejection_efficiency", "must be between 0 and 1")?;
+        config::check(config::positive(self.min_fragment_mass), "collision_mode.min_fragment_mass", "must be positive")
+    }
+}
+
+/// Merges two bodies into one at their center of mass, conserving mass, charge, linear and angular
+/// momentum. The radius is derived from the combined volume, treating bodies as spheres of equal
+/// density.
+///
+/// A static or kinematic body absorbs the other in place, keeping its position and motion. The
+/// merged body keeps the id of the absorbing (or heavier) body.
+pub fn merge(a: &Body, b: &Body) -> Body {
+    if let Some(anchor) = [a, b].into_iter().find(|body| !body.is_dynamic()) {
+        let mut merged = *anchor;
+        merged.mass = a.mass + b.mass;
+        merged.charge = a.charge + b.charge;
+        merged.radius = (a.radius.powi(3) + b.radius.powi(3)).cbrt();
+        return merged;
+    }
+
+    let mass = a.mass + b.mass;
+    // massless bodies meet halfway
+    let center = |pa: Vector, pb: Vector| if mass > 0.0 { (pa * a.mass + pb * b.mass) / mass } else { (pa + pb) / 2.0 };
+    let position = center(a.position, b.position);
+    let velocity = center(a.velocity, b.velocity);
+    let radius = (a.radius.powi(3) + b.radius.powi(3)).cbrt();
+
+    let mut merged = Body::new(position, velocity, mass, radius);
+    merged.charge = a.charge + b.charge;
+    let inertia = merged.moment_of_inertia();
+    if inertia > 0.0 {
+        merged.angular_velocity = angular_momentum(a, b, position, velocity) / inertia;
+    }
+    let dominant = dominant_body(a, b);
+    merged.material = dominant.material;
+    merged.id = dominant.id;
+    merged
+}
+
+/// The heavier body, whose material (and id, when merging) the products of a collision inherit.
+fn dominant_body<'a>(a: &'a Body, b: &'a Body) -> &'a Body {
+    if a.mass >= b.mass { a } else { b }
+}
+
+/// Total angular momentum of two bodies (spins plus orbital motion) about `center` moving with
+/// `velocity`.
+fn angular_momentum(a: &Body, b: &Body, center: Vector, velocity: Vector) -> Spin {
+    a.spin_angular_momentum()
+        + b.spin_angular_momentum()
+        + (a.position - center).cross(a.velocity - velocity) * a.mass
+        + (b.position - center).cross(b.velocity - velocity) * b.mass
+}
+
+/// Shatters two colliding bodies into fragments if their specific impact energy exceeds the
+/// threshold. Returns `None` if the collision is too gentle, the fragments would be too small, or
+/// either body is static or kinematic.
+///
+/// Fragments conserve total mass, charge (shared in proportion to mass), volume, center of mass,
+/// linear and angular momentum. They are placed on a ring around the center of mass
+/// (perpendicular to the angular momentum in 3D), ejected radially with a share of the impact
+/// energy, and the ring as a whole spins rigidly to carry the angular momentum of the collision.
+pub fn fragment(a: &Body, b: &Body, params: &Fragmentation, rng: &mut impl Rng) -> Option<Vec<Body>> {
+    if !a.is_dynamic() || !b.is_dynamic() {
+        return None;
+    }
+    let mass = a.mass + b.mass;
+    let reduced_mass = a.mass * b.mass / mass;
+    let impact_energy = 0.5 * reduced_mass * (b.velocity - a.velocity).length_squared();
+    if impact_energy / mass <= params.energy_threshold {
+        return None;
+    }
+
+    // fragment masses follow a power law by rank, dropping the smallest until all are heavy enough
+    let mut masses = vec![];
+    for count in (2..=params.max_fragments).rev() {
+        let weights: Vec<Scalar> = (1..=count).map(|k| (k as Scalar).powf(-params.mass_exponent)).collect();
+        let total: Scalar = weights.iter().sum();
+        masses = weights.iter().map(|w| mass * w / total).collect();
+        if masses[masses.len() - 1] >= params.min_fragment_mass {
+            break;
+        }
+        masses.clear();
+    }
+    if masses.is_empty() {
+        return None;
+    }
+
+    // same density as the parents, so total volume is conserved
+    let volume_per_mass = (a.radius.powi(3) + b.radius.powi(3)) / mass;
+    let radii: Vec<Scalar> = masses.iter().map(|m| (m * volume_per_mass).cbrt()).collect();
+
+    // ring wide enough that neighbouring fragments don't overlap
+    let n = masses.len();
+    let ring_radius = radii[0] * 1.01 / (PI / n as Scalar).sin();
+    let ejection_speed = (2.0 * params.ejection_efficiency * impact_energy / mass).sqrt();
+    let center_of_mass = (a.position * a.mass + b.position * b.mass) / mass;
+    let velocity = (a.velocity * a.mass + b.velocity * b.mass) / mass;
+    let angle_offset = rng.random::<Scalar>() * 2.0 * PI;
+    let angular_momentum = angular_momentum(a, b, center_of_mass, velocity);
+
+    let directions: Vec<Vector> = (0..n)
+        .map(|k| Vector::on_circle(angular_momentum, angle_offset + 2.0 * PI * k as Scalar / n as Scalar))
+        .collect();
+
+    // the ring is lopsided for unequal masses, subtract its mass-weighted mean direction so the
+    // center of mass and momentum are unchanged
+    let mean_direction = directions
+        .iter()
+        .zip(&masses)
+        .fold(Vector::zero(), |acc, (&d, &m)| acc + d * m) / mass;
+
+    let mut fragments: Vec<Body> = directions
+        .iter()
+        .zip(masses.iter().zip(&radii))
+        .map(|(&direction, (&m, &r))| {
+            let offset = direction - mean_direction;
+            Body::new(
+                center_of_mass + offset * ring_radius,
+                velocity + offset * ejection_speed,
+                m,
+                r,
+            )
+        })
+        .collect();
+
+    // rigid rotation of the ring, w = L / (sum of spin and orbital moments of inertia)
+    let ring_inertia: Scalar = fragments
+        .iter()
+        .map(|f| f.moment_of_inertia() + f.mass * (f.position - center_of_mass).length_squared())
+        .sum();
+    let spin = angular_momentum / ring_inertia;
+    for fragment in fragments.iter_mut() {
+        fragment.velocity += (fragment.position - center_of_mass).spin_velocity(spin);
+        fragment.angular_velocity = spin;
+        fragment.material = dominant_body(a, b).material;
+        fragment.charge = (a.charge + b.charge) * fragment.mass / mass;
+    }
+    Some(fragments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merging_massless_point_bodies_stays_finite() {
+        let a = Body::new(Vector::zero(), Vector::unit(0), 0.0, 0.0);
+        let b = Body::new(Vector::unit(1), Vector::zero(), 0.0, 0.0);
+        let merged = merge(&a, &b);
+        assert_eq!(merged.position, Vector::unit(1) / 2.0);
+        assert_eq!(merged.velocity, Vector::unit(0) / 2.0);
+        assert_eq!(merged.angular_velocity, Spin::default());
+    }
+}
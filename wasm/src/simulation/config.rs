@@ -0,0 +1,126 @@
+use std::fmt;
+
+use serde::Deserialize;
+
+use crate::simulation::boundary::Boundary;
+use crate::simulation::broadphase::Broadphase;
+use crate::simulation::collision::CollisionMode;
+use crate::simulation::contact_solver::ContactSolverSettings;
+use crate::simulation::force_field::ForceField;
+use crate::simulation::force_law::ForceLawKind;
+use crate::simulation::gravity::GravitySolver;
+use crate::simulation::integrator::IntegratorKind;
+use crate::simulation::material::Material;
+use crate::simulation::scenario::Scenario;
+use crate::simulation::softening::Softening;
+use crate::simulation::vec2::{Scalar, Vector};
+
+/// Every parameter of a simulation, including the scenario generating the initial bodies. Missing
+/// fields take their default, so a config can be given as a partial (e.g. javascript) object.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct SimulationConfig {
+    /// Seed of all randomness in the simulation, chosen at random if not set.
+    pub seed: Option<u64>,
+    pub scenario: Scenario,
+    pub grav_constant: Scalar,
+    pub softening: Softening,
+    pub gravity_solver: GravitySolver,
+    pub integrator: IntegratorKind,
+    /// Default material (index 0), used by every generated body and the container walls.
+    pub material: Material,
+    pub collision_mode: CollisionMode,
+    pub contact_solver: ContactSolverSettings,
+    pub broadphase: Broadphase,
+    pub continuous_collisions: bool,
+    pub boundary: Boundary,
+    pub force_fields: Vec<ForceField>,
+    /// Pairwise interactions on top of gravity, e.g. electrostatics between charged bodies.
+    pub force_laws: Vec<ForceLawKind>,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            seed: None,
+            scenario: Scenario::default(),
+            grav_constant: 600.0,
+            softening: Softening::Plummer { length: 0.03 },
+            gravity_solver: GravitySolver::Direct,
+            integrator: IntegratorKind::SemiImplicitEuler,
+            material: Material::default(),
+            collision_mode: CollisionMode::Bounce,
+            contact_solver: ContactSolverSettings::default(),
+            broadphase: Broadphase::SpatialHash,
+            continuous_collisions: false,
+            boundary: Boundary::Open,
+            force_fields: vec![],
+            force_laws: vec![],
+        }
+    }
+}
+
+/// A config parameter with a nonsensical value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigError {
+    pub field: &'static str,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid {}: {}", self.field, self.reason)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Returns an error for `field` unless `valid`.
+pub fn check(valid: bool, field: &'static str, reason: &'static str) -> Result<(), ConfigError> {
+    if valid { Ok(()) } else { Err(ConfigError { field, reason }) }
+}
+
+pub fn positive(x: Scalar) -> bool {
+    x.is_finite() && x > 0.0
+}
+
+pub fn non_negative(x: Scalar) -> bool {
+    x.is_finite() && x >= 0.0
+}
+
+/// Returns whether every component of `v` is finite.
+pub fn finite(v: Vector) -> bool {
+    v.axes().into_iter().all(Scalar::is_finite)
+}
+
+pub fn fraction(x: Scalar) -> bool {
+    (0.0..=1.0).contains(&x)
+}
+
+impl SimulationConfig {
+    /// Checks that every parameter is in its valid range, returning the first one that isn't.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.scenario.validate()?;
+        check(non_negative(self.grav_constant), "grav_constant", "must be finite and non-negative")?;
+
+        self.softening.validate()?;
+        self.gravity_solver.validate()?;
+
+        self.material.validate()?;
+
+        self.collision_mode.validate()?;
+
+        self.contact_solver.validate()?;
+
+        self.boundary.validate()?;
+
+        for field in &self.force_fields {
+            field.validate()?;
+        }
+
+        for law in &self.force_laws {
+            law.validate()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::simulation::body::{Body, BodyId};
+use crate::simulation::boundary::Boundary;
+use crate::simulation::config::{self, ConfigError};
+use crate::simulation::material::{Material, PairMaterial};
+use crate::simulation::vec2::{Scalar, Vector};
+
+/// Number of friction directions of a contact, one in 2D and two in 3D.
+const TANGENTS: usize = Vector::DIM - 1;
+
+/// Method used to push overlapping bodies apart.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+pub enum PositionCorrection {
+    /// Baumgarte stabilization: a bias proportional to the penetration is added to the velocity
+    /// constraint. Simple, but the correction ends up as real velocity (bodies can pop apart).
+    Baumgarte { beta: Scalar, slop: Scalar },
+    /// Split impulses: penetration is solved with separate pseudo-velocities that only move
+    /// positions, so the correction doesn't add energy.
+    SplitImpulse { beta: Scalar, slop: Scalar },
+}
+
+/// Settings of the contact solver.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ContactSolverSettings {
+    /// Number of sequential impulse iterations per step. More iterations give stiffer, more
+    /// order-independent stacks.
+    pub iterations: u32,
+    pub position_correction: PositionCorrection,
+    /// Reuse the impulses of the previous step as a starting guess for contacts that persist.
+    pub warm_starting: bool,
+    /// Approach speeds below this don't bounce, so resting contacts can settle.
+    pub restitution_threshold: Scalar,
+}
+
+impl Default for ContactSolverSettings {
+    fn default() -> Self {
+        ContactSolverSettings {
+            iterations: 8,
+            position_correction: PositionCorrection::SplitImpulse { beta: 0.8, slop: 0.01 },
+            warm_starting: true,
+            restitution_threshold: 1.0,
+        }
+    }
+}
+
+impl ContactSolverSettings {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        config::check(self.iterations >= 1, "contact_solver.iterations", "must be at least 1")?;
+        let (PositionCorrection::Baumgarte { beta, slop } | PositionCorrection::SplitImpulse { beta, slop }) =
+            self.position_correction;
+        config::check(config::fraction(beta), "contact_solver.position_correction.beta", "must be between 0 and 1")?;
+        config::check(config::non_negative(slop), "contact_solver.position_correction.slop", "must be non-negative")?;
+        config::check(
+            config::non_negative(self.restitution_threshold),
+            "contact_solver.restitution_threshold",
+            "must be non-negative",
+        )
+    }
+}
+
+/// A contact between body `i` and body `j` (or a container wall, if `None`), with the normal
+/// pointing from `i` to `j`.
+struct Contact {
+    i: usize,
+    j: Option<usize>,
+    // identifies the contact between steps for warm starting
+    key: (BodyId, BodyId),
+    normal: Vector,
+    tangents: [Vector; TANGENTS],
+    // contact point relative to each body's center
+    arm_i: Vector,
+    arm_j: Vector,
+    penetration: Scalar,
+    // effective masses along the normal and tangents (the tangents include rotational inertia)
+    normal_mass: Scalar,
+    tangent_mass: Scalar,
+    // target normal velocity from restitution
+    velocity_bias: Scalar,
+    static_friction: Scalar,
+    dynamic_friction: Scalar,
+    normal_impulse: Scalar,
+    tangent_impulse: [Scalar; TANGENTS],
+    pseudo_impulse: Scalar,
+}
+
+/// Iterative sequential-impulse contact solver, caching contact impulses between steps for warm
+/// starting.
+pub struct ContactSolver {
+    pub settings: ContactSolverSettings,
+    // accumulated (normal, tangent) impulses of last step's contacts, keyed by body id pair
+    // (walls use ids counting down from u32::MAX)
+    cache: HashMap<(BodyId, BodyId), (Scalar, [Scalar; TANGENTS])>,
+}
+
+impl ContactSolver {
+    pub fn new(settings: ContactSolverSettings) -> Self {
+        ContactSolver {
+            settings,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Forgets cached impulses, e.g. when the container walls change.
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Solves all contacts among the candidate `pairs` and against the container walls, updating
+    /// velocities (and positions, with split impulses).
+    pub fn solve(&mut self, bodies: &mut [Body], pairs: &[(usize, usize)], boundary: &Boundary, materials: &[Material], dt: Scalar) {
+        let mut contacts = self.find_contacts(bodies, pairs, boundary, materials);
+        contacts.extend(self.find_wall_contacts(bodies, boundary, materials));
+
+        if self.settings.warm_starting {
+            for contact in contacts.iter_mut() {
+                if let Some(&(normal_impulse, tangent_impulse)) = self.cache.get(&contact.key) {
+                    contact.normal_impulse = normal_impulse;
+                    contact.tangent_impulse = tangent_impulse;
+                    let impulse = contact.normal * normal_impulse + tangent_sum(contact, tangent_impulse);
+                    apply_impulse(bodies, contact, impulse);
+                }
+            }
+        }
+
+        let (beta, slop, split) = match self.settings.position_correction {
+            PositionCorrection::Baumgarte { beta, slop } => (beta, slop, false),
+            PositionCorrection::SplitImpulse { beta, slop } => (beta, slop, true),
+        };
+
+        // velocity iterations
+        for _ in 0..self.settings.iterations {
+            for contact in contacts.iter_mut() {
+                // friction at the contact point (creates torque on both bodies). The contact sticks
+                // while the impulse is inside the static coulomb cone, otherwise it slides with
+                // dynamic friction
+                let relative_velocity = contact_velocity(bodies, contact);
+                let old_impulse = contact.tangent_impulse;
+                let mut sticking_impulse = old_impulse;
+                for (impulse, tangent) in sticking_impulse.iter_mut().zip(contact.tangents) {
+                    *impulse -= relative_velocity.dot(tangent) * contact.tangent_mass;
+                }
+                let magnitude = sticking_impulse.iter().map(|i| i * i).sum::<Scalar>().sqrt();
+                contact.tangent_impulse = if magnitude <= contact.static_friction * contact.normal_impulse {
+                    sticking_impulse
+                } else {
+                    let max_friction = contact.dynamic_friction * contact.normal_impulse;
+                    sticking_impulse.map(|i| i * (max_friction / magnitude))
+                };
+                let change = std::array::from_fn(|k| contact.tangent_impulse[k] - old_impulse[k]);
+                apply_impulse(bodies, contact, tangent_sum(contact, change));
+
+                // non-penetration, the accumulated normal impulse can only push
+                let relative_velocity = contact_velocity(bodies, contact);
+                let mut bias = contact.velocity_bias;
+                if !split {
+                    bias += beta / dt * (contact.penetration - slop).max(0.0);
+                }
+                let lambda = (bias - relative_velocity.dot(contact.normal)) * contact.normal_mass;
+                let old_impulse = contact.normal_impulse;
+                contact.normal_impulse = (old_impulse + lambda).max(0.0);
+                apply_impulse(bodies, contact, contact.normal * (contact.normal_impulse - old_impulse));
+            }
+        }
+
+        // position iterations on pseudo-velocities
+        if split {
+            let mut pseudo_velocities = vec![Vector::zero(); bodies.len()];
+            for _ in 0..self.settings.iterations {
+                for contact in contacts.iter_mut() {
+                    let i = contact.i;
+                    let pseudo_velocity_j = contact.j.map_or(Vector::zero(), |j| pseudo_velocities[j]);
+                    let relative_velocity = pseudo_velocity_j - pseudo_velocities[i];
+                    let bias = beta / dt * (contact.penetration - slop).max(0.0);
+                    let lambda = (bias - relative_velocity.dot(contact.normal)) * contact.normal_mass;
+                    let old_impulse = contact.pseudo_impulse;
+                    contact.pseudo_impulse = (old_impulse + lambda).max(0.0);
+                    let impulse = contact.normal * (contact.pseudo_impulse - old_impulse);
+                    pseudo_velocities[i] -= impulse * bodies[i].inverse_mass();
+                    if let Some(j) = contact.j {
+                        pseudo_velocities[j] += impulse * bodies[j].inverse_mass();
+                    }
+                }
+            }
+            for (body, pseudo_velocity) in bodies.iter_mut().zip(pseudo_velocities) {
+                body.position += pseudo_velocity * dt;
+            }
+        }
+
+        self.cache = contacts
+            .iter()
+            .map(|c| (c.key, (c.normal_impulse, c.tangent_impulse)))
+            .collect();
+    }
+
+    fn find_contacts(&self, bodies: &[Body], pairs: &[(usize, usize)], boundary: &Boundary, materials: &[Material]) -> Vec<Contact> {
+        let mut contacts = vec![];
+        for &(i, j) in pairs {
+            let (a, b) = (&bodies[i], &bodies[j]);
+            let inv_mass = a.inverse_mass() + b.inverse_mass();
+            if inv_mass == 0.0 {
+                continue; // static and kinematic bodies pass through each other
+            }
+            let direction = boundary.displacement(a.position, b.position);
+            let distance = direction.length();
+            let penetration = a.radius + b.radius - distance;
+            if penetration <= 0.0 {
+                continue;
+            }
+
+            // arbitrary normal for coincident bodies
+            let normal = if distance > 0.0 { direction / distance } else { Vector::unit(0) };
+            let material = PairMaterial::of_bodies(materials, a, b);
+            let approach_speed = -(b.velocity - a.velocity).dot(normal); // spin has no normal component
+            let velocity_bias = if approach_speed > self.settings.restitution_threshold {
+                material.restitution * approach_speed
+            } else {
+                0.0
+            };
+
+            // contact point in the middle of the overlap, so both arms reach the same point and
+            // friction conserves angular momentum. The arms are along the normal, so every tangent
+            // sees the same rotational inertia (|arm x tangent| = |arm|)
+            let (arm_i, arm_j) = (normal * (a.radius - penetration / 2.0), normal * -(b.radius - penetration / 2.0));
+            let tangent_inv_mass = inv_mass
+                + arm_i.length_squared() * a.inverse_inertia()
+                + arm_j.length_squared() * b.inverse_inertia();
+
+            contacts.push(Contact {
+                i,
+                j: Some(j),
+                key: (a.id, b.id),
+                normal,
+                tangents: normal.tangents(),
+                arm_i,
+                arm_j,
+                penetration,
+                normal_mass: 1.0 / inv_mass,
+                tangent_mass: 1.0 / tangent_inv_mass,
+                velocity_bias,
+                static_friction: material.static_friction,
+                dynamic_friction: material.dynamic_friction,
+                normal_impulse: 0.0,
+                tangent_impulse: [0.0; TANGENTS],
+                pseudo_impulse: 0.0,
+            });
+        }
+        contacts
+    }
+
+    /// Finds contacts between dynamic bodies and the container walls. Walls are static with infinite
+    /// mass.
+    fn find_wall_contacts(&self, bodies: &[Body], boundary: &Boundary, materials: &[Material]) -> Vec<Contact> {
+        let Some(wall_material) = boundary.material() else {
+            return vec![];
+        };
+
+        let mut contacts = vec![];
+        for (i, body) in bodies.iter().enumerate().filter(|(_, b)| b.is_dynamic()) {
+            let inv_mass = body.inverse_mass();
+            if inv_mass == 0.0 {
+                continue; // massless bodies can't be pushed back by the walls
+            }
+            for wall in boundary.wall_contacts(body) {
+                let normal = wall.normal;
+                let material = PairMaterial::of_indices(materials, body.material, wall_material);
+                let approach_speed = body.velocity.dot(normal);
+                let velocity_bias = if approach_speed > self.settings.restitution_threshold {
+                    material.restitution * approach_speed
+                } else {
+                    0.0
+                };
+
+                let arm_i = normal * (body.radius - wall.penetration / 2.0);
+                let tangent_inv_mass = inv_mass + arm_i.length_squared() * body.inverse_inertia();
+
+                contacts.push(Contact {
+                    i,
+                    j: None,
+                    key: (body.id, BodyId(u32::MAX - wall.wall as u32)),
+                    normal,
+                    tangents: normal.tangents(),
+                    arm_i,
+                    arm_j: Vector::zero(),
+                    penetration: wall.penetration,
+                    normal_mass: 1.0 / inv_mass,
+                    tangent_mass: 1.0 / tangent_inv_mass,
+                    velocity_bias,
+                    static_friction: material.static_friction,
+                    dynamic_friction: material.dynamic_friction,
+                    normal_impulse: 0.0,
+                    tangent_impulse: [0.0; TANGENTS],
+                    pseudo_impulse: 0.0,
+                });
+            }
+        }
+        contacts
+    }
+}
+
+/// Velocity of body `j` (or the static wall) relative to body `i` at the contact point, including spin.
+fn contact_velocity(bodies: &[Body], contact: &Contact) -> Vector {
+    let a = &bodies[contact.i];
+    let velocity_i = a.velocity + contact.arm_i.spin_velocity(a.angular_velocity);
+    let velocity_j = match contact.j {
+        Some(j) => bodies[j].velocity + contact.arm_j.spin_velocity(bodies[j].angular_velocity),
+        None => Vector::zero(),
+    };
+    velocity_j - velocity_i
+}
+
+/// Friction impulse with the given components along the contact tangents.
+fn tangent_sum(contact: &Contact, impulses: [Scalar; TANGENTS]) -> Vector {
+    contact
+        .tangents
+        .iter()
+        .zip(impulses)
+        .fold(Vector::zero(), |sum, (&tangent, impulse)| sum + tangent * impulse)
+}
+
+/// Applies `impulse` to body `j` and the opposite impulse to body `i`, at the contact point.
+fn apply_impulse(bodies: &mut [Body], contact: &Contact, impulse: Vector) {
+    let i = contact.i;
+    bodies[i].velocity -= impulse * bodies[i].inverse_mass();
+    bodies[i].angular_velocity -= contact.arm_i.cross(impulse) * bodies[i].inverse_inertia();
+    if let Some(j) = contact.j {
+        bodies[j].velocity += impulse * bodies[j].inverse_mass();
+        bodies[j].angular_velocity += contact.arm_j.cross(impulse) * bodies[j].inverse_inertia();
+    }
+}
@@ -0,0 +1,58 @@
+use crate::simulation::body::Body;
+use crate::simulation::vec2::{Scalar, Spin, Vector};
+
+/// Conserved quantities and global properties of the simulation state, to track drift while tuning
+/// the time step and collision parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Diagnostics {
+    /// Translational plus rotational kinetic energy.
+    pub kinetic_energy: Scalar,
+    /// Potential energy of mutual gravity, consistent with the softening kernel.
+    pub gravitational_energy: Scalar,
+    /// Potential energy of the force laws other than gravity (e.g. electrostatics).
+    pub interaction_energy: Scalar,
+    /// Potential energy in the external force fields.
+    pub external_energy: Scalar,
+    /// Sum of kinetic and potential energies.
+    pub total_energy: Scalar,
+    pub momentum: Vector,
+    /// Orbital plus spin angular momentum about the origin.
+    pub angular_momentum: Spin,
+    pub center_of_mass: Vector,
+    pub total_mass: Scalar,
+    /// 2K / |W| for kinetic energy K and gravitational energy W, 1 for a system in virial equilibrium.
+    pub virial_ratio: Scalar,
+}
+
+impl Diagnostics {
+    /// Measures `bodies`, given their gravitational, interaction and external potential energies.
+    /// In a periodic domain, the center of mass and angular momentum depend on where bodies were
+    /// wrapped.
+    pub fn measure(bodies: &[Body], gravitational_energy: Scalar, interaction_energy: Scalar, external_energy: Scalar) -> Self {
+        let mut kinetic_energy = 0.0;
+        let mut momentum = Vector::zero();
+        let mut angular_momentum = Spin::default();
+        let mut weighted_position = Vector::zero();
+        let mut total_mass = 0.0;
+        for body in bodies {
+            kinetic_energy += 0.5 * body.mass * body.velocity.length_squared() + body.rotational_energy();
+            momentum += body.velocity * body.mass;
+            angular_momentum += body.position.cross(body.velocity) * body.mass + body.spin_angular_momentum();
+            weighted_position += body.position * body.mass;
+            total_mass += body.mass;
+        }
+
+        Diagnostics {
+            kinetic_energy,
+            gravitational_energy,
+            interaction_energy,
+            external_energy,
+            total_energy: kinetic_energy + gravitational_energy + interaction_energy + external_energy,
+            momentum,
+            angular_momentum,
+            center_of_mass: if total_mass > 0.0 { weighted_position / total_mass } else { Vector::zero() },
+            total_mass,
+            virial_ratio: if gravitational_energy != 0.0 { 2.0 * kinetic_energy / gravitational_energy.abs() } else { 0.0 },
+        }
+    }
+}
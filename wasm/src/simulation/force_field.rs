@@ -0,0 +1,116 @@
+use serde::Deserialize;
+
+use crate::simulation::body::Body;
+use crate::simulation::boundary::Boundary;
+use crate::simulation::config::{self, ConfigError};
+use crate::simulation::gravity::Gravity;
+use crate::simulation::vec2::{Scalar, Vector};
+
+/// An external field adding an acceleration to every body, on top of mutual gravity. Fields with a
+/// center use the nearest periodic image of it.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+pub enum ForceField {
+    /// Constant acceleration, e.g. (0, -9.81) for gravity near a surface.
+    Uniform { acceleration: Vector },
+    /// Fixed point mass at `center`, using the simulation's gravitational constant and softening.
+    PointMass { center: Vector, mass: Scalar },
+    /// Logarithmic halo, potential v²/2 · ln(r² + core_radius²). Gives a flat rotation curve at
+    /// `circular_velocity` well outside the core.
+    LogarithmicHalo { center: Vector, circular_velocity: Scalar, core_radius: Scalar },
+    /// Navarro-Frenk-White halo with scale radius `scale_radius`, where `mass` is 4π·ρ₀·rₛ³ (the
+    /// enclosed mass is `mass` · (ln(1 + x) - x / (1 + x)) at x = r / rₛ).
+    NfwHalo { center: Vector, mass: Scalar, scale_radius: Scalar },
+    /// Drag deceleration proportional to velocity, -coefficient · v.
+    LinearDrag { coefficient: Scalar },
+    /// Drag deceleration proportional to the squared speed, -coefficient · |v| · v.
+    QuadraticDrag { coefficient: Scalar },
+}
+
+impl ForceField {
+    /// Checks that the field's parameters are finite, with positive radii and non-negative drag.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        match *self {
+            ForceField::Uniform { acceleration } => {
+                config::check(config::finite(acceleration), "force_fields.acceleration", "must be finite")
+            }
+            ForceField::PointMass { center, mass } => {
+                config::check(config::finite(center), "force_fields.center", "must be finite")?;
+                config::check(mass.is_finite(), "force_fields.mass", "must be finite")
+            }
+            ForceField::LogarithmicHalo { center, circular_velocity, core_radius } => {
+                config::check(config::finite(center), "force_fields.center", "must be finite")?;
+                config::check(circular_velocity.is_finite(), "force_fields.circular_velocity", "must be finite")?;
+                config::check(config::positive(core_radius), "force_fields.core_radius", "must be positive")
+            }
+            ForceField::NfwHalo { center, mass, scale_radius } => {
+                config::check(config::finite(center), "force_fields.center", "must be finite")?;
+                config::check(mass.is_finite(), "force_fields.mass", "must be finite")?;
+                config::check(config::positive(scale_radius), "force_fields.scale_radius", "must be positive")
+            }
+            ForceField::LinearDrag { coefficient } | ForceField::QuadraticDrag { coefficient } => {
+                config::check(config::non_negative(coefficient), "force_fields.coefficient", "must be non-negative")
+            }
+        }
+    }
+
+    /// Acceleration of `body` due to the field.
+    pub fn acceleration(&self, body: &Body, gravity: &Gravity, boundary: &Boundary) -> Vector {
+        match *self {
+            ForceField::Uniform { acceleration } => acceleration,
+            ForceField::PointMass { center, mass } => {
+                gravity.pair_acceleration(boundary.displacement(body.position, center), mass)
+            }
+            ForceField::LogarithmicHalo { center, circular_velocity, core_radius } => {
+                let direction = boundary.displacement(body.position, center);
+                let v2 = circular_velocity * circular_velocity;
+                direction * (v2 / (direction.length_squared() + core_radius * core_radius))
+            }
+            ForceField::NfwHalo { center, mass, scale_radius } => {
+                let direction = boundary.displacement(body.position, center);
+                let r = direction.length();
+                if r == 0.0 {
+                    return Vector::zero();
+                }
+                let x = r / scale_radius;
+                let enclosed_mass = mass * ((1.0 + x).ln() - x / (1.0 + x));
+                direction * (gravity.grav_constant * enclosed_mass / (r * r * r))
+            }
+            ForceField::LinearDrag { coefficient } => body.velocity * -coefficient,
+            ForceField::QuadraticDrag { coefficient } => body.velocity * (-coefficient * body.velocity.length()),
+        }
+    }
+
+    /// Potential energy per unit mass of a body at `position`, or 0 for fields without a potential
+    /// (drag).
+    pub fn potential(&self, position: Vector, gravity: &Gravity, boundary: &Boundary) -> Scalar {
+        match *self {
+            ForceField::Uniform { acceleration } => -acceleration.dot(position),
+            ForceField::PointMass { center, mass } => {
+                let distance_sq = boundary.displacement(position, center).length_squared();
+                gravity.grav_constant * mass * gravity.softening.potential(distance_sq)
+            }
+            ForceField::LogarithmicHalo { center, circular_velocity, core_radius } => {
+                let distance_sq = boundary.displacement(position, center).length_squared();
+                0.5 * circular_velocity * circular_velocity * (distance_sq + core_radius * core_radius).ln()
+            }
+            ForceField::NfwHalo { center, mass, scale_radius } => {
+                let r = boundary.displacement(position, center).length();
+                if r == 0.0 {
+                    return -gravity.grav_constant * mass / scale_radius; // limit at the center
+                }
+                -gravity.grav_constant * mass * (1.0 + r / scale_radius).ln() / r
+            }
+            ForceField::LinearDrag { .. } | ForceField::QuadraticDrag { .. } => 0.0,
+        }
+    }
+}
+
+/// Adds the acceleration of every field to `accelerations`.
+pub fn add_accelerations(fields: &[ForceField], bodies: &[Body], gravity: &Gravity, boundary: &Boundary, accelerations: &mut [Vector]) {
+    for field in fields {
+        for (accel, body) in accelerations.iter_mut().zip(bodies) {
+            *accel += field.acceleration(body, gravity, boundary);
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use serde::Deserialize;
+
+use crate::simulation::body::Body;
+use crate::simulation::boundary::Boundary;
+use crate::simulation::config::{self, ConfigError};
+use crate::simulation::parallel;
+use crate::simulation::softening::Softening;
+use crate::simulation::vec2::{Scalar, Vector};
+
+/// A pairwise interaction between bodies. Accelerations and energies of several laws add up, so
+/// e.g. charged particles can feel both gravity and electrostatics. In a periodic domain, each
+/// body only feels the nearest image of every other body.
+pub trait ForceLaw: Sync {
+    /// Acceleration of `body` due to `other`, located at `direction` relative to it.
+    fn acceleration(&self, body: &Body, other: &Body, direction: Vector) -> Vector;
+
+    /// Potential energy of the pair `a`, `b` at squared distance `distance_sq`.
+    fn pair_potential(&self, a: &Body, b: &Body, distance_sq: Scalar) -> Scalar;
+
+    /// Computes the acceleration of every body. By default sums over every pair of bodies,
+    /// processed in parallel with the `parallel` feature.
+    fn accelerations(&self, bodies: &[Body], boundary: &Boundary) -> Vec<Vector> {
+        parallel::map_indices(bodies.len(), |i| {
+            let mut accel = Vector::zero();
+            for (j, other) in bodies.iter().enumerate() {
+                if i != j {
+                    accel += self.acceleration(&bodies[i], other, boundary.displacement(bodies[i].position, other.position));
+                }
+            }
+            accel
+        })
+    }
+
+    /// Total potential energy of all pairs of bodies.
+    fn potential_energy(&self, bodies: &[Body], boundary: &Boundary) -> Scalar {
+        let mut energy = 0.0;
+        for (i, a) in bodies.iter().enumerate() {
+            for b in &bodies[i + 1..] {
+                energy += self.pair_potential(a, b, boundary.displacement(a.position, b.position).length_squared());
+            }
+        }
+        energy
+    }
+}
+
+/// Electrostatic force between charges, `coulomb_constant` · q₁q₂ / r². Like charges repel.
+/// Softened like gravity, so oppositely charged bodies don't accelerate without bound.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct Coulomb {
+    pub coulomb_constant: Scalar,
+    pub softening: Softening,
+}
+
+impl ForceLaw for Coulomb {
+    fn acceleration(&self, body: &Body, other: &Body, direction: Vector) -> Vector {
+        let strength = self.coulomb_constant * body.charge * other.charge;
+        direction * (-strength * self.softening.force_factor(direction.length_squared()) * inverse_mass(body))
+    }
+
+    fn pair_potential(&self, a: &Body, b: &Body, distance_sq: Scalar) -> Scalar {
+        // softening potentials are attractive (negative), like charges have positive energy
+        -self.coulomb_constant * a.charge * b.charge * self.softening.potential(distance_sq)
+    }
+}
+
+/// Lennard-Jones potential 4ε((σ/r)¹² - (σ/r)⁶), strongly repulsive below `sigma` and weakly
+/// attractive beyond, with its minimum -ε at 2^(1/6)·σ. Cut off at `cutoff` (typically 2.5σ), and
+/// shifted so the potential is continuous there.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct LennardJones {
+    pub epsilon: Scalar,
+    pub sigma: Scalar,
+    pub cutoff: Scalar,
+}
+
+impl LennardJones {
+    fn unshifted_potential(&self, distance_sq: Scalar) -> Scalar {
+        let s6 = (self.sigma * self.sigma / distance_sq).powi(3);
+        4.0 * self.epsilon * (s6 * s6 - s6)
+    }
+}
+
+impl ForceLaw for LennardJones {
+    fn acceleration(&self, body: &Body, _other: &Body, direction: Vector) -> Vector {
+        let distance_sq = direction.length_squared();
+        if distance_sq == 0.0 || distance_sq >= self.cutoff * self.cutoff {
+            return Vector::zero();
+        }
+        // -dV/dr / r, positive when repulsive
+        let s6 = (self.sigma * self.sigma / distance_sq).powi(3);
+        let force_over_r = 24.0 * self.epsilon * (2.0 * s6 * s6 - s6) / distance_sq;
+        direction * (-force_over_r * inverse_mass(body))
+    }
+
+    fn pair_potential(&self, _a: &Body, _b: &Body, distance_sq: Scalar) -> Scalar {
+        if distance_sq == 0.0 || distance_sq >= self.cutoff * self.cutoff {
+            return 0.0;
+        }
+        self.unshifted_potential(distance_sq) - self.unshifted_potential(self.cutoff * self.cutoff)
+    }
+}
+
+/// Screened Coulomb (Yukawa) interaction between charges, potential
+/// `strength` · q₁q₂ · e^(-r / `screening_length`) / r, as in dusty plasmas and electrolytes.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct Yukawa {
+    pub strength: Scalar,
+    pub screening_length: Scalar,
+}
+
+impl ForceLaw for Yukawa {
+    fn acceleration(&self, body: &Body, other: &Body, direction: Vector) -> Vector {
+        let r = direction.length();
+        if r == 0.0 {
+            return Vector::zero();
+        }
+        let potential = self.strength * body.charge * other.charge * (-r / self.screening_length).exp() / r;
+        // -dV/dr / r, positive when repulsive
+        let force_over_r = potential * (1.0 / r + 1.0 / self.screening_length) / r;
+        direction * (-force_over_r * inverse_mass(body))
+    }
+
+    fn pair_potential(&self, a: &Body, b: &Body, distance_sq: Scalar) -> Scalar {
+        let r = distance_sq.sqrt();
+        if r == 0.0 {
+            return 0.0;
+        }
+        self.strength * a.charge * b.charge * (-r / self.screening_length).exp() / r
+    }
+}
+
+/// Hooke-like spring between every pair of bodies closer than `cutoff`, pulling (or pushing) them
+/// towards `rest_length` with force `stiffness` · (r - rest_length). The potential is shifted to
+/// be continuous at the cutoff.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct Spring {
+    pub stiffness: Scalar,
+    pub rest_length: Scalar,
+    pub cutoff: Scalar,
+}
+
+impl ForceLaw for Spring {
+    fn acceleration(&self, body: &Body, _other: &Body, direction: Vector) -> Vector {
+        let r = direction.length();
+        if r == 0.0 || r >= self.cutoff {
+            return Vector::zero();
+        }
+        direction * (self.stiffness * (r - self.rest_length) / r * inverse_mass(body))
+    }
+
+    fn pair_potential(&self, _a: &Body, _b: &Body, distance_sq: Scalar) -> Scalar {
+        let r = distance_sq.sqrt();
+        if r >= self.cutoff {
+            return 0.0;
+        }
+        let stretch = r - self.rest_length;
+        let cutoff_stretch = self.cutoff - self.rest_length;
+        0.5 * self.stiffness * (stretch * stretch - cutoff_stretch * cutoff_stretch)
+    }
+}
+
+/// 1 / mass, or 0 for massless bodies, which forces proportional to charge can't accelerate.
+fn inverse_mass(body: &Body) -> Scalar {
+    if body.mass > 0.0 { 1.0 / body.mass } else { 0.0 }
+}
+
+/// The available force laws on top of gravity, selectable by name with their parameters (e.g.
+/// from a `SimulationConfig`).
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+pub enum ForceLawKind {
+    Coulomb(Coulomb),
+    LennardJones(LennardJones),
+    Yukawa(Yukawa),
+    Spring(Spring),
+}
+
+impl ForceLawKind {
+    /// Checks that the law's strength is finite and its lengths are in range.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        match *self {
+            ForceLawKind::Coulomb(Coulomb { coulomb_constant, softening }) => {
+                config::check(coulomb_constant.is_finite(), "force_laws.coulomb_constant", "must be finite")?;
+                match softening {
+                    Softening::None => Ok(()),
+                    Softening::Plummer { length } | Softening::Spline { length } => config::check(
+                        config::non_negative(length),
+                        "force_laws.softening.length",
+                        "must be finite and non-negative",
+                    ),
+                }
+            }
+            ForceLawKind::LennardJones(LennardJones { epsilon, sigma, cutoff }) => {
+                config::check(epsilon.is_finite(), "force_laws.epsilon", "must be finite")?;
+                config::check(config::positive(sigma), "force_laws.sigma", "must be positive")?;
+                config::check(config::positive(cutoff), "force_laws.cutoff", "must be positive")
+            }
+            ForceLawKind::Yukawa(Yukawa { strength, screening_length }) => {
+                config::check(strength.is_finite(), "force_laws.strength", "must be finite")?;
+                config::check(config::positive(screening_length), "force_laws.screening_length", "must be positive")
+            }
+            ForceLawKind::Spring(Spring { stiffness, rest_length, cutoff }) => {
+                config::check(stiffness.is_finite(), "force_laws.stiffness", "must be finite")?;
+                config::check(config::non_negative(rest_length), "force_laws.rest_length", "must be finite and non-negative")?;
+                config::check(config::positive(cutoff), "force_laws.cutoff", "must be positive")
+            }
+        }
+    }
+
+    pub fn build(self) -> Box<dyn ForceLaw> {
+        match self {
+            ForceLawKind::Coulomb(law) => Box::new(law),
+            ForceLawKind::LennardJones(law) => Box::new(law),
+            ForceLawKind::Yukawa(law) => Box::new(law),
+            ForceLawKind::Spring(law) => Box::new(law),
+        }
+    }
+}
+
+/// Adds the acceleration of every law to `accelerations`.
+pub fn add_accelerations(laws: &[Box<dyn ForceLaw>], bodies: &[Body], boundary: &Boundary, accelerations: &mut [Vector]) {
+    for law in laws {
+        for (accel, law_accel) in accelerations.iter_mut().zip(law.accelerations(bodies, boundary)) {
+            *accel += law_accel;
+        }
+    }
+}
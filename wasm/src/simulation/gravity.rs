@@ -0,0 +1,136 @@
+use serde::Deserialize;
+
+use crate::simulation::body::Body;
+use crate::simulation::boundary::Boundary;
+use crate::simulation::config::{self, ConfigError};
+use crate::simulation::force_law::ForceLaw;
+use crate::simulation::parallel;
+use crate::simulation::quadtree::QuadTree;
+#[cfg(not(feature = "f64"))]
+use crate::simulation::soa;
+use crate::simulation::soa::BodyArrays;
+use crate::simulation::softening::Softening;
+use crate::simulation::vec2::{Scalar, Vector};
+
+/// Algorithm used to compute the gravitational acceleration on every body.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+pub enum GravitySolver {
+    /// Exact pairwise summation, O(n²).
+    Direct,
+    /// Barnes-Hut quadtree (octree in 3D) approximation, O(n log n). Nodes whose size over distance is below
+    /// `theta` are treated as a single point mass (smaller is more accurate, 0 is exact).
+    BarnesHut { theta: Scalar },
+}
+
+impl GravitySolver {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        match *self {
+            GravitySolver::Direct => Ok(()),
+            GravitySolver::BarnesHut { theta } => {
+                config::check(config::non_negative(theta), "gravity_solver.theta", "must be finite and non-negative")
+            }
+        }
+    }
+}
+
+/// Gravity parameters: strength, softening kernel and the solver used to sum forces.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gravity {
+    pub grav_constant: Scalar,
+    pub softening: Softening,
+    pub solver: GravitySolver,
+}
+
+impl Gravity {
+    /// Computes accelerations by summing over every pair of bodies, one pair at a time. Supports
+    /// every softening kernel and boundary, and serves as the reference for the vectorized kernel.
+    pub fn direct_accelerations(&self, bodies: &[Body], boundary: &Boundary) -> Vec<Vector> {
+        parallel::map_indices(bodies.len(), |i| {
+            let mut accel = Vector::zero();
+            for (j, other) in bodies.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                let direction = boundary.displacement(bodies[i].position, other.position);
+                accel += self.pair_acceleration(direction, other.mass);
+            }
+            accel
+        })
+    }
+
+    /// Computes the gravitational acceleration on every body with the configured solver, like
+    /// `ForceLaw::accelerations`, with the vectorized kernel reading `arrays`, which must hold the
+    /// state of `bodies` (e.g. the simulation's persistent arrays, synced before every call).
+    #[cfg_attr(feature = "f64", allow(unused_variables))] // the vectorized kernel is f32 only
+    pub fn accelerations_with(&self, bodies: &[Body], boundary: &Boundary, arrays: &BodyArrays) -> Vec<Vector> {
+        // gravity can be turned off, e.g. for molecular dynamics with other force laws only
+        if self.grav_constant == 0.0 {
+            return vec![Vector::zero(); bodies.len()];
+        }
+        match self.solver {
+            GravitySolver::Direct => match (self.softening, boundary) {
+                // vectorized f32 kernel for the common case, the scalar one handles everything else
+                #[cfg(not(feature = "f64"))]
+                (Softening::None, Boundary::Open | Boundary::Box { .. } | Boundary::Circle { .. }) => {
+                    soa::direct_accelerations(arrays, self.grav_constant, 0.0)
+                }
+                #[cfg(not(feature = "f64"))]
+                (Softening::Plummer { length }, Boundary::Open | Boundary::Box { .. } | Boundary::Circle { .. }) => {
+                    soa::direct_accelerations(arrays, self.grav_constant, length * length)
+                }
+                _ => self.direct_accelerations(bodies, boundary),
+            },
+            GravitySolver::BarnesHut { theta } => {
+                let tree = QuadTree::new(bodies);
+                parallel::map_indices(bodies.len(), |i| {
+                    tree.acceleration(i, &bodies[i], theta, self, boundary)
+                })
+            }
+        }
+    }
+
+    /// Acceleration towards a point mass `mass` located at `direction` relative to the body.
+    pub fn pair_acceleration(&self, direction: Vector, mass: Scalar) -> Vector {
+        direction * (self.grav_constant * mass * self.softening.force_factor(direction.length_squared()))
+    }
+}
+
+/// Newtonian gravity as a force law, alongside the others. Accelerations use the configured solver.
+impl ForceLaw for Gravity {
+    fn acceleration(&self, _body: &Body, other: &Body, direction: Vector) -> Vector {
+        self.pair_acceleration(direction, other.mass)
+    }
+
+    fn pair_potential(&self, a: &Body, b: &Body, distance_sq: Scalar) -> Scalar {
+        self.grav_constant * a.mass * b.mass * self.softening.potential(distance_sq)
+    }
+
+    /// Computes the gravitational acceleration on every body with the configured solver. Bodies
+    /// are processed in parallel with the `parallel` feature, each summing its forces in a fixed
+    /// order so results don't depend on the thread count.
+    fn accelerations(&self, bodies: &[Body], boundary: &Boundary) -> Vec<Vector> {
+        self.accelerations_with(bodies, boundary, &BodyArrays::new(bodies))
+    }
+}
+
+/// Root-mean-square relative error of `approx` against the `exact` accelerations, used to measure
+/// the accuracy of an approximate solver.
+pub fn relative_error(approx: &[Vector], exact: &[Vector]) -> Scalar {
+    if exact.is_empty() {
+        return 0.0;
+    }
+    let sum_sq: Scalar = approx
+        .iter()
+        .zip(exact)
+        .map(|(&a, &e)| {
+            let magnitude = e.length();
+            if magnitude > 0.0 {
+                (a - e).length_squared() / (magnitude * magnitude)
+            } else {
+                0.0
+            }
+        })
+        .sum();
+    (sum_sq / exact.len() as Scalar).sqrt()
+}
@@ -0,0 +1,130 @@
+use serde::Deserialize;
+
+use crate::simulation::body::Body;
+use crate::simulation::vec2::{Scalar, Vector};
+
+/// Computes the acceleration of every body for a given state.
+pub type AccelerationFn<'a> = dyn Fn(&[Body]) -> Vec<Vector> + 'a;
+
+/// A time integration scheme, advancing body positions and velocities by one time step.
+pub trait Integrator {
+    /// Advances `bodies` by `dt`, evaluating `accelerations` as many times as the scheme requires.
+    fn step(&self, bodies: &mut [Body], dt: Scalar, accelerations: &AccelerationFn);
+}
+
+/// Semi-implicit (symplectic) Euler: kick velocities, then drift positions. First order, one force
+/// evaluation per step.
+pub struct SemiImplicitEuler;
+
+impl Integrator for SemiImplicitEuler {
+    fn step(&self, bodies: &mut [Body], dt: Scalar, accelerations: &AccelerationFn) {
+        kick(bodies, &accelerations(bodies), dt);
+        drift(bodies, dt);
+    }
+}
+
+/// Leapfrog in kick-drift-kick form (equivalent to velocity Verlet). Second order and symplectic,
+/// two force evaluations per step.
+pub struct Leapfrog;
+
+impl Integrator for Leapfrog {
+    fn step(&self, bodies: &mut [Body], dt: Scalar, accelerations: &AccelerationFn) {
+        kick(bodies, &accelerations(bodies), dt / 2.0);
+        drift(bodies, dt);
+        kick(bodies, &accelerations(bodies), dt / 2.0);
+    }
+}
+
+/// Classic fourth order Runge-Kutta. Accurate over short times but not symplectic, so energy
+/// drifts secularly over long integrations. Four force evaluations per step.
+pub struct Rk4;
+
+impl Integrator for Rk4 {
+    fn step(&self, bodies: &mut [Body], dt: Scalar, accelerations: &AccelerationFn) {
+        let x0: Vec<Vector> = bodies.iter().map(|b| b.position).collect();
+        let v0: Vec<Vector> = bodies.iter().map(|b| b.velocity).collect();
+        let mut trial = bodies.to_vec();
+
+        // evaluates (dx/dt, dv/dt) at the state offset from the start by `h` times the given derivatives
+        let mut derivatives = |h: Scalar, dx: &[Vector], dv: &[Vector]| {
+            for (k, body) in trial.iter_mut().enumerate() {
+                body.position = x0[k] + dx[k] * h;
+                body.velocity = v0[k] + dv[k] * h;
+            }
+            let v: Vec<Vector> = trial.iter().map(|b| b.velocity).collect();
+            (v, accelerations(&trial))
+        };
+
+        let zero = vec![Vector::zero(); bodies.len()];
+        let (k1x, k1v) = derivatives(0.0, &zero, &zero);
+        let (k2x, k2v) = derivatives(dt / 2.0, &k1x, &k1v);
+        let (k3x, k3v) = derivatives(dt / 2.0, &k2x, &k2v);
+        let (k4x, k4v) = derivatives(dt, &k3x, &k3v);
+
+        for (k, body) in bodies.iter_mut().enumerate() {
+            body.position = x0[k] + (k1x[k] + (k2x[k] + k3x[k]) * 2.0 + k4x[k]) * (dt / 6.0);
+            body.velocity = v0[k] + (k1v[k] + (k2v[k] + k3v[k]) * 2.0 + k4v[k]) * (dt / 6.0);
+            // no torques, so spin is constant (3D bodies don't track their orientation)
+            #[cfg(not(feature = "dim3"))]
+            {
+                body.angle += body.angular_velocity * dt;
+            }
+        }
+    }
+}
+
+/// Fourth order symplectic integrator of Yoshida / Forest-Ruth, composing three leapfrog steps
+/// with weights chosen to cancel the third order error. Three force evaluations per step.
+pub struct Yoshida4;
+
+impl Integrator for Yoshida4 {
+    fn step(&self, bodies: &mut [Body], dt: Scalar, accelerations: &AccelerationFn) {
+        let cbrt2 = (2.0 as Scalar).cbrt();
+        let w1 = 1.0 / (2.0 - cbrt2);
+        let w0 = -cbrt2 / (2.0 - cbrt2);
+        let c = [w1 / 2.0, (w0 + w1) / 2.0, (w0 + w1) / 2.0, w1 / 2.0];
+        let d = [w1, w0, w1];
+
+        for k in 0..3 {
+            drift(bodies, c[k] * dt);
+            kick(bodies, &accelerations(bodies), d[k] * dt);
+        }
+        drift(bodies, c[3] * dt);
+    }
+}
+
+/// The available integration schemes, selectable by name (e.g. from a `SimulationConfig`).
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum IntegratorKind {
+    SemiImplicitEuler,
+    Leapfrog,
+    Rk4,
+    Yoshida4,
+}
+
+impl IntegratorKind {
+    pub fn build(self) -> Box<dyn Integrator> {
+        match self {
+            IntegratorKind::SemiImplicitEuler => Box::new(SemiImplicitEuler),
+            IntegratorKind::Leapfrog => Box::new(Leapfrog),
+            IntegratorKind::Rk4 => Box::new(Rk4),
+            IntegratorKind::Yoshida4 => Box::new(Yoshida4),
+        }
+    }
+}
+
+fn kick(bodies: &mut [Body], accelerations: &[Vector], dt: Scalar) {
+    for (body, &accel) in bodies.iter_mut().zip(accelerations) {
+        body.velocity += accel * dt;
+    }
+}
+
+fn drift(bodies: &mut [Body], dt: Scalar) {
+    for body in bodies.iter_mut() {
+        body.position += body.velocity * dt;
+        #[cfg(not(feature = "dim3"))]
+        {
+            body.angle += body.angular_velocity * dt;
+        }
+    }
+}
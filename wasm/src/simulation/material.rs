@@ -0,0 +1,93 @@
+use serde::Deserialize;
+
+use crate::simulation::body::Body;
+use crate::simulation::config::{self, ConfigError};
+use crate::simulation::vec2::Scalar;
+
+/// How the property values of two materials are combined for a contact between them. When the
+/// two materials use different rules, the one later in this list wins (as in PhysX).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub enum CombineRule {
+    Average,
+    Min,
+    Multiply,
+    Max,
+}
+
+impl CombineRule {
+    pub fn combine(self, a: Scalar, b: Scalar) -> Scalar {
+        match self {
+            CombineRule::Average => (a + b) / 2.0,
+            CombineRule::Min => a.min(b),
+            CombineRule::Multiply => a * b,
+            CombineRule::Max => a.max(b),
+        }
+    }
+}
+
+/// Contact properties shared by a group of bodies, referenced by index from `Body::material`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Material {
+    /// Coefficient of restitution, 0 is perfectly inelastic and 1 perfectly elastic.
+    pub restitution: Scalar,
+    /// Coulomb friction coefficient holding contacts at rest relative to each other.
+    pub static_friction: Scalar,
+    /// Coulomb friction coefficient of sliding contacts.
+    pub dynamic_friction: Scalar,
+    pub restitution_combine: CombineRule,
+    pub friction_combine: CombineRule,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            restitution: 0.95,
+            static_friction: 0.5,
+            dynamic_friction: 0.5,
+            restitution_combine: CombineRule::Average,
+            friction_combine: CombineRule::Average,
+        }
+    }
+}
+
+impl Material {
+    /// Checks that the restitution is between 0 and 1 and the friction coefficients non-negative.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        config::check(config::fraction(self.restitution), "material.restitution", "must be between 0 and 1")?;
+        config::check(config::non_negative(self.static_friction), "material.static_friction", "must be non-negative")?;
+        config::check(config::non_negative(self.dynamic_friction), "material.dynamic_friction", "must be non-negative")
+    }
+}
+
+/// Contact properties of a pair of materials, after applying the combine rules.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PairMaterial {
+    pub restitution: Scalar,
+    pub static_friction: Scalar,
+    pub dynamic_friction: Scalar,
+}
+
+impl PairMaterial {
+    /// Looks up and combines the materials of two bodies. Unknown material indices fall back to
+    /// the default material (index 0).
+    pub fn of_bodies(materials: &[Material], a: &Body, b: &Body) -> Self {
+        PairMaterial::of_indices(materials, a.material, b.material)
+    }
+
+    /// Looks up and combines two materials by index, falling back to the default material.
+    pub fn of_indices(materials: &[Material], a: u32, b: u32) -> Self {
+        let lookup = |index: u32| materials.get(index as usize).or(materials.first()).copied().unwrap_or_default();
+        PairMaterial::combine(&lookup(a), &lookup(b))
+    }
+
+    pub fn combine(a: &Material, b: &Material) -> Self {
+        let restitution_rule = a.restitution_combine.max(b.restitution_combine);
+        let friction_rule = a.friction_combine.max(b.friction_combine);
+        PairMaterial {
+            restitution: restitution_rule.combine(a.restitution, b.restitution),
+            static_friction: friction_rule.combine(a.static_friction, b.static_friction),
+            dynamic_friction: friction_rule.combine(a.dynamic_friction, b.dynamic_friction),
+        }
+    }
+}
@@ -1,6 +1,48 @@
+#[allow(clippy::module_inception)]
 mod simulation;
 mod vec2;
+#[cfg(feature = "dim3")]
+mod vec3;
 mod body;
+mod boundary;
+mod broadphase;
+mod ccd;
+mod collision;
+mod config;
+mod contact_solver;
+mod diagnostics;
+mod force_field;
+mod force_law;
+mod gravity;
+mod integrator;
+mod material;
+mod parallel;
+mod quadtree;
+mod scenario;
+#[cfg(not(feature = "f64"))]
+mod simd;
+mod soa;
+mod softening;
+mod spatial_index;
 
 pub use simulation::Simulation;
-pub use body::Body;
\ No newline at end of file
+pub use body::{Body, BodyId, BodyKind};
+pub use boundary::Boundary;
+pub use broadphase::Broadphase;
+pub use collision::{CollisionMode, Fragmentation};
+pub use config::{ConfigError, SimulationConfig};
+pub use contact_solver::{ContactSolverSettings, PositionCorrection};
+pub use diagnostics::Diagnostics;
+pub use force_field::ForceField;
+pub use force_law::{Coulomb, ForceLaw, ForceLawKind, LennardJones, Spring, Yukawa};
+pub use gravity::{Gravity, GravitySolver};
+pub use material::{CombineRule, Material};
+pub use scenario::{
+    ColdCollapse, ExponentialDisk, GalaxyCollision, GranularPile, PlanetarySystem, PlummerSphere, RandomSquare, Scenario,
+};
+pub use soa::BodyArrays;
+pub use softening::Softening;
+pub use vec2::{Scalar, Spin, Vec2, Vector};
+#[cfg(feature = "dim3")]
+pub use vec3::Vec3;
+pub use integrator::{Integrator, IntegratorKind, SemiImplicitEuler, Leapfrog, Rk4, Yoshida4};
\ No newline at end of file
@@ -0,0 +1,16 @@
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Computes `f(i)` for every `i` in `0..n`, spread over the rayon thread pool with the `parallel`
+/// feature. Items are computed independently and collected in order, so the result is the same
+/// for any number of threads.
+#[cfg(feature = "parallel")]
+pub fn map_indices<T: Send>(n: usize, f: impl Fn(usize) -> T + Sync + Send) -> Vec<T> {
+    (0..n).into_par_iter().map(f).collect()
+}
+
+/// Computes `f(i)` for every `i` in `0..n` (single-threaded without the `parallel` feature).
+#[cfg(not(feature = "parallel"))]
+pub fn map_indices<T: Send>(n: usize, f: impl Fn(usize) -> T + Sync + Send) -> Vec<T> {
+    (0..n).map(f).collect()
+}
@@ -0,0 +1,208 @@
+use crate::simulation::body::Body;
+use crate::simulation::boundary::Boundary;
+use crate::simulation::gravity::Gravity;
+use crate::simulation::vec2::{Scalar, Vector};
+
+/// Children of a subdivided node, four in 2D and eight (an octree) in 3D.
+const CHILDREN: usize = 1 << Vector::DIM;
+
+/// Beyond this depth, bodies are lumped into the same leaf instead of subdividing further
+/// (prevents infinite subdivision for coincident bodies).
+const MAX_DEPTH: u32 = 32;
+
+/// A node of the quadtree, covering the square (cube in 3D) `center ± half_size`.
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    center: Vector,
+    half_size: Scalar,
+    mass: Scalar,
+    center_of_mass: Vector,
+    // index of the first of `CHILDREN` contiguous children, if subdivided
+    children: Option<usize>,
+    // index of the body stored in this leaf, if any (the first one, for leaves lumping several)
+    body: Option<usize>,
+}
+
+impl Node {
+    fn new(center: Vector, half_size: Scalar) -> Self {
+        Node {
+            center,
+            half_size,
+            mass: 0.0,
+            center_of_mass: Vector::zero(),
+            children: None,
+            body: None,
+        }
+    }
+
+    /// Index (0..CHILDREN) of the child quadrant (octant in 3D) containing `position`, with bit k
+    /// set if it lies on the positive side along axis k.
+    fn quadrant(&self, position: Vector) -> usize {
+        let (position, center) = (position.axes(), self.center.axes());
+        (0..Vector::DIM).map(|k| ((position[k] >= center[k]) as usize) << k).sum()
+    }
+}
+
+/// Barnes-Hut quadtree (octree in 3D) over a set of bodies, storing the total mass and center of mass of every node.
+pub struct QuadTree {
+    nodes: Vec<Node>,
+    // index of the leaf holding every body, so a body can leave itself out of its leaf
+    leaf_of: Vec<usize>,
+}
+
+impl QuadTree {
+    /// Builds a quadtree containing all given bodies.
+    pub fn new(bodies: &[Body]) -> Self {
+        // bounding square (cube) of all bodies
+        let mut min = Vector::splat(Scalar::INFINITY);
+        let mut max = Vector::splat(Scalar::NEG_INFINITY);
+        for body in bodies {
+            min = min.min(body.position);
+            max = max.max(body.position);
+        }
+        let (center, half_size) = if bodies.is_empty() {
+            (Vector::zero(), 1.0)
+        } else {
+            let size = (max - min).axes().into_iter().fold(0.0, Scalar::max);
+            ((min + max) / 2.0, (size / 2.0).max(1e-3) * 1.001)
+        };
+
+        let mut tree = QuadTree {
+            nodes: Vec::with_capacity(2 * bodies.len() + 1),
+            leaf_of: vec![0; bodies.len()],
+        };
+        tree.nodes.push(Node::new(center, half_size));
+        for (i, body) in bodies.iter().enumerate() {
+            tree.insert(bodies, i, body);
+        }
+
+        // convert mass-weighted position sums into centers of mass
+        for node in tree.nodes.iter_mut() {
+            if node.mass > 0.0 {
+                node.center_of_mass /= node.mass;
+            }
+        }
+        tree
+    }
+
+    fn insert(&mut self, bodies: &[Body], index: usize, body: &Body) {
+        let mut node_index = 0;
+        let mut depth = 0;
+        loop {
+            let node = &mut self.nodes[node_index];
+            node.mass += body.mass;
+            node.center_of_mass += body.position * body.mass;
+
+            if let Some(children) = node.children {
+                node_index = children + node.quadrant(body.position);
+                depth += 1;
+                continue;
+            }
+            if node.body.is_none() && node.mass == body.mass {
+                // empty leaf, store the body here
+                node.body = Some(index);
+                self.leaf_of[index] = node_index;
+                return;
+            }
+            if depth >= MAX_DEPTH {
+                // lump body into this leaf
+                self.leaf_of[index] = node_index;
+                return;
+            }
+
+            // occupied leaf, subdivide and push the existing body down one level
+            let existing = node.body.take();
+            let (center, quarter) = (node.center, node.half_size / 2.0);
+            let children = self.nodes.len();
+            self.nodes[node_index].children = Some(children);
+            for quadrant in 0..CHILDREN {
+                let offset = Vector::from_axes(std::array::from_fn(|k| if quadrant >> k & 1 == 1 { 1.0 } else { -1.0 }));
+                self.nodes.push(Node::new(center + offset * quarter, quarter));
+            }
+            if let Some(existing) = existing {
+                let existing_body = &bodies[existing];
+                let quadrant = self.nodes[node_index].quadrant(existing_body.position);
+                let child = &mut self.nodes[children + quadrant];
+                child.mass = existing_body.mass;
+                child.center_of_mass = existing_body.position * existing_body.mass;
+                child.body = Some(existing);
+                self.leaf_of[existing] = children + quadrant;
+            }
+            node_index = children + self.nodes[node_index].quadrant(body.position);
+            depth += 1;
+        }
+    }
+
+    /// Approximates the gravitational acceleration on body `index`, opening every node whose size
+    /// over distance is at least `theta`. Distances to nodes use the nearest periodic image, which
+    /// is only meaningful for nodes narrower than half the domain, so wider nodes are always opened.
+    pub fn acceleration(&self, index: usize, body: &Body, theta: Scalar, gravity: &Gravity, boundary: &Boundary) -> Vector {
+        let max_size = match *boundary {
+            Boundary::Periodic { min, max } => (max - min).axes().into_iter().fold(Scalar::INFINITY, Scalar::min) / 2.0,
+            _ => Scalar::INFINITY,
+        };
+        let mut accel = Vector::zero();
+        let theta_sq = theta * theta;
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            let (mut mass, mut center_of_mass) = (node.mass, node.center_of_mass);
+            if node_index == self.leaf_of[index] {
+                // leave the body itself out of its leaf, keeping any others lumped with it
+                mass -= body.mass;
+                if mass <= 0.0 {
+                    continue;
+                }
+                center_of_mass = (node.center_of_mass * node.mass - body.position * body.mass) / mass;
+            }
+            if mass == 0.0 {
+                continue;
+            }
+            let direction = boundary.displacement(body.position, center_of_mass);
+            let size = 2.0 * node.half_size;
+            match node.children {
+                Some(children) if size > max_size || size * size >= theta_sq * direction.length_squared() => {
+                    stack.extend(children..children + CHILDREN);
+                }
+                _ => accel += gravity.pair_acceleration(direction, mass),
+            }
+        }
+        accel
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::force_law::ForceLaw;
+    use crate::simulation::gravity::{self, GravitySolver};
+    use crate::simulation::softening::Softening;
+
+    fn gravity(solver: GravitySolver) -> Gravity {
+        Gravity { grav_constant: 1.0, softening: Softening::None, solver }
+    }
+
+    fn barnes_hut_error(bodies: &[Body], theta: Scalar, boundary: &Boundary) -> Scalar {
+        let exact = gravity(GravitySolver::Direct).accelerations(bodies, boundary);
+        let approx = gravity(GravitySolver::BarnesHut { theta }).accelerations(bodies, boundary);
+        gravity::relative_error(&approx, &exact)
+    }
+
+    #[test]
+    fn bodies_lumped_into_one_leaf_attract_each_other() {
+        // closer than the smallest node, so both end up in the same leaf at the maximum depth
+        let positions = [Vector::splat(1.0), Vector::splat(1.0) + Vector::unit(0) * 1e-12, Vector::splat(-5.0), Vector::splat(5.0)];
+        let bodies: Vec<Body> = positions.into_iter().map(|p| Body::new(p, Vector::zero(), 1.0, 0.1)).collect();
+        assert!(barnes_hut_error(&bodies, 0.0, &Boundary::Open) < 1e-6);
+    }
+
+    #[test]
+    fn nodes_wider_than_half_a_periodic_domain_are_opened() {
+        // the outer bodies are neighbours across the domain edge, far from the center of mass, and
+        // every body lands in its own quadrant of the root
+        let boundary = Boundary::Periodic { min: Vector::splat(-5.0), max: Vector::splat(5.0) };
+        let positions = [Vector::planar(-4.5, -1.0), Vector::planar(0.5, -1.0), Vector::planar(4.5, 1.0)];
+        let bodies: Vec<Body> = positions.into_iter().map(|p| Body::new(p, Vector::zero(), 1.0, 0.1)).collect();
+        assert!(barnes_hut_error(&bodies, 1000.0, &boundary) < 1e-6);
+    }
+}
@@ -0,0 +1,629 @@
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::simulation::body::{Body, BodyKind};
+use crate::simulation::boundary::Boundary;
+use crate::simulation::config::{self, ConfigError, SimulationConfig};
+use crate::simulation::contact_solver::ContactSolverSettings;
+use crate::simulation::force_field::ForceField;
+use crate::simulation::force_law::ForceLaw;
+use crate::simulation::gravity::{Gravity, GravitySolver};
+use crate::simulation::integrator::IntegratorKind;
+use crate::simulation::material::Material;
+use crate::simulation::softening::Softening;
+use crate::simulation::vec2::consts::PI;
+use crate::simulation::vec2::{Scalar, Vector};
+
+/// A named generator of initial conditions, with its parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+pub enum Scenario {
+    RandomSquare(RandomSquare),
+    ExponentialDisk(ExponentialDisk),
+    GalaxyCollision(GalaxyCollision),
+    PlummerSphere(PlummerSphere),
+    PlanetarySystem(PlanetarySystem),
+    ColdCollapse(ColdCollapse),
+    GranularPile(GranularPile),
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Scenario::RandomSquare(RandomSquare::default())
+    }
+}
+
+impl Scenario {
+    /// Generates the initial bodies. Equilibrium velocities are derived from `gravity`.
+    pub fn generate(&self, gravity: &Gravity, rng: &mut impl Rng) -> Vec<Body> {
+        match self {
+            Scenario::RandomSquare(s) => s.generate(rng),
+            Scenario::ExponentialDisk(s) => s.generate(gravity, rng),
+            Scenario::GalaxyCollision(s) => s.generate(gravity, rng),
+            Scenario::PlummerSphere(s) => s.generate(gravity, rng),
+            Scenario::PlanetarySystem(s) => s.generate(gravity, rng),
+            Scenario::ColdCollapse(s) => s.generate(gravity, rng),
+            Scenario::GranularPile(s) => s.generate(rng),
+        }
+    }
+
+    /// Returns a simulation config suited to the scenario (solver, softening, fields, container),
+    /// using it to generate the bodies.
+    pub fn config(&self) -> SimulationConfig {
+        let scenario = *self;
+        // stars should be collisionless, so their rare contacts are made elastic and frictionless
+        // (inelastic contacts would slowly drain energy and make the core collapse)
+        let galactic = SimulationConfig {
+            scenario,
+            softening: Softening::Plummer { length: 1.0 },
+            gravity_solver: GravitySolver::BarnesHut { theta: 0.5 },
+            integrator: IntegratorKind::Leapfrog,
+            material: Material { restitution: 1.0, static_friction: 0.0, dynamic_friction: 0.0, ..Material::default() },
+            contact_solver: ContactSolverSettings { restitution_threshold: 0.0, ..ContactSolverSettings::default() },
+            ..SimulationConfig::default()
+        };
+        match self {
+            Scenario::RandomSquare(_) => SimulationConfig { scenario, ..SimulationConfig::default() },
+            Scenario::ExponentialDisk(_) | Scenario::GalaxyCollision(_) => galactic,
+            Scenario::PlummerSphere(_) | Scenario::ColdCollapse(_) => galactic,
+            Scenario::PlanetarySystem(_) => SimulationConfig {
+                scenario,
+                integrator: IntegratorKind::Leapfrog,
+                ..SimulationConfig::default()
+            },
+            Scenario::GranularPile(pile) => SimulationConfig {
+                scenario,
+                grav_constant: 0.0,
+                material: Material { restitution: 0.2, ..Material::default() },
+                boundary: pile.container(),
+                force_fields: vec![ForceField::Uniform { acceleration: Vector::planar(0.0, -pile.gravity) }],
+                ..SimulationConfig::default()
+            },
+        }
+    }
+
+    /// Checks that the scenario parameters are in their valid range.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        match self {
+            Scenario::RandomSquare(s) => {
+                config::check(config::non_negative(s.size), "scenario.size", "must be finite and non-negative")?;
+                check_positive(s.mass, "scenario.mass")?;
+                check_positive(s.radius, "scenario.radius")?;
+                config::check(s.orbital_speed.is_finite(), "scenario.orbital_speed", "must be finite")
+            }
+            Scenario::ExponentialDisk(s) => s.validate(),
+            Scenario::GalaxyCollision(s) => {
+                s.galaxy.validate()?;
+                config::check(config::non_negative(s.separation), "scenario.separation", "must be non-negative")
+            }
+            Scenario::PlummerSphere(s) => {
+                check_positive(s.total_mass, "scenario.total_mass")?;
+                check_positive(s.body_radius, "scenario.body_radius")?;
+                check_positive(s.scale_radius, "scenario.scale_radius")?;
+                config::check(config::non_negative(s.virial_ratio), "scenario.virial_ratio", "must be non-negative")
+            }
+            Scenario::PlanetarySystem(s) => {
+                check_positive(s.star_mass, "scenario.star_mass")?;
+                check_positive(s.star_radius, "scenario.star_radius")?;
+                check_positive(s.planet_mass, "scenario.planet_mass")?;
+                check_positive(s.planet_radius, "scenario.planet_radius")?;
+                check_positive(s.ring_mass, "scenario.ring_mass")?;
+                check_positive(s.ring_particle_radius, "scenario.ring_particle_radius")?;
+                check_positive(s.inner_orbit, "scenario.inner_orbit")?;
+                let planet_clear = s.inner_orbit > s.star_radius + s.planet_radius;
+                config::check(planet_clear, "scenario.inner_orbit", "must keep the innermost planet clear of the star")?;
+                config::check(s.orbit_spacing >= 1.0, "scenario.orbit_spacing", "must be at least 1")?;
+                check_positive(s.ring_inner_radius, "scenario.ring_inner_radius")?;
+                let clear_of_star = s.ring_inner_radius > s.star_radius + s.ring_particle_radius;
+                config::check(clear_of_star, "scenario.ring_inner_radius", "must leave the ring clear of the star")?;
+                check_positive(s.ring_outer_radius, "scenario.ring_outer_radius")?;
+                config::check(s.ring_inner_radius <= s.ring_outer_radius, "scenario.ring_inner_radius", "must not exceed the outer radius")
+            }
+            Scenario::ColdCollapse(s) => {
+                check_positive(s.total_mass, "scenario.total_mass")?;
+                check_positive(s.body_radius, "scenario.body_radius")?;
+                check_positive(s.radius, "scenario.radius")?;
+                config::check(config::non_negative(s.virial_ratio), "scenario.virial_ratio", "must be non-negative")
+            }
+            Scenario::GranularPile(s) => {
+                check_positive(s.grain_mass, "scenario.grain_mass")?;
+                check_positive(s.grain_radius, "scenario.grain_radius")?;
+                config::check(s.column_width >= 2.0 * s.grain_radius, "scenario.column_width", "must fit at least one grain")?;
+                config::check(s.width >= s.column_width, "scenario.width", "must be at least the column width")?;
+                config::check(config::non_negative(s.gravity), "scenario.gravity", "must be non-negative")
+            }
+        }
+    }
+}
+
+fn check_positive(value: Scalar, field: &'static str) -> Result<(), ConfigError> {
+    config::check(config::positive(value), field, "must be positive")
+}
+
+/// Bodies at rest (or swirling) scattered uniformly in a square (cube in 3D) centered on the origin.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RandomSquare {
+    pub num_bodies: usize,
+    /// Side length of the square (cube).
+    pub size: Scalar,
+    pub mass: Scalar,
+    pub radius: Scalar,
+    /// Initial clockwise swirl around the origin (about the z axis), bodies at distance r move at
+    /// `orbital_speed / sqrt(r + 0.1)`. Bodies start at rest if 0.
+    pub orbital_speed: Scalar,
+}
+
+impl Default for RandomSquare {
+    fn default() -> Self {
+        RandomSquare {
+            num_bodies: 500,
+            size: 500.0,
+            mass: 5.0,
+            radius: 1.0,
+            orbital_speed: 0.0,
+        }
+    }
+}
+
+impl RandomSquare {
+    fn generate(&self, rng: &mut impl Rng) -> Vec<Body> {
+        let mut bodies = vec![];
+        for _ in 0..self.num_bodies {
+            let mut pos = Vector::from_axes(std::array::from_fn(|_| rng.random()));
+            pos = (pos - Vector::splat(0.5)) * self.size;
+            let vel = Vector::planar(pos.y, -pos.x).normalize() * (1.0 / (pos.length() + 0.1).sqrt()) * self.orbital_speed;
+            bodies.push(Body::new(pos, vel, self.mass, self.radius));
+        }
+        bodies
+    }
+}
+
+/// A rotating disk galaxy: bodies with an exponential surface density on circular orbits around a
+/// central mass, with a little random velocity dispersion. The disk lies in the xy plane, tilted by
+/// `inclination` in 3D.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ExponentialDisk {
+    pub num_bodies: usize,
+    /// Total mass of the disk bodies.
+    pub disk_mass: Scalar,
+    /// Radius over which the surface density drops by a factor e.
+    pub scale_length: Scalar,
+    /// Mass of the central body (bulge or black hole), none if 0.
+    pub central_mass: Scalar,
+    pub central_radius: Scalar,
+    pub body_radius: Scalar,
+    /// Random velocity dispersion as a fraction of the circular velocity.
+    pub dispersion: Scalar,
+    pub center: Vector,
+    pub velocity: Vector,
+    /// Rotation of the disk about the x axis, in radians.
+    #[cfg(feature = "dim3")]
+    pub inclination: Scalar,
+}
+
+impl Default for ExponentialDisk {
+    fn default() -> Self {
+        ExponentialDisk {
+            num_bodies: 1000,
+            disk_mass: 2000.0,
+            scale_length: 60.0,
+            central_mass: 10000.0,
+            central_radius: 5.0,
+            body_radius: 0.5,
+            dispersion: 0.05,
+            center: Vector::zero(),
+            velocity: Vector::zero(),
+            #[cfg(feature = "dim3")]
+            inclination: 0.0,
+        }
+    }
+}
+
+impl ExponentialDisk {
+    fn generate(&self, gravity: &Gravity, rng: &mut impl Rng) -> Vec<Body> {
+        let body_mass = self.disk_mass / self.num_bodies.max(1) as Scalar;
+        let inner_edge = if self.central_mass > 0.0 { self.central_radius + self.body_radius } else { 0.0 };
+
+        // the enclosed mass of an exponential disk follows a gamma(2) distribution, sampled as the
+        // sum of two exponentials. Sorted, so the enclosed mass is known at every radius
+        let mut radii: Vec<Scalar> = (0..self.num_bodies)
+            .map(|_| inner_edge - self.scale_length * (unit_open(rng) * unit_open(rng)).ln())
+            .collect();
+        radii.sort_by(Scalar::total_cmp);
+
+        let mut bodies = vec![];
+        for (k, &r) in radii.iter().enumerate() {
+            let enclosed_mass = self.central_mass + body_mass * k as Scalar;
+            let circular_speed = (gravity.grav_constant * enclosed_mass / r).sqrt();
+            let direction = random_planar_direction(rng);
+            let velocity = self.tilt(direction.perp()) * circular_speed + gaussian(rng) * (self.dispersion * circular_speed);
+            bodies.push(Body::new(
+                self.center + self.tilt(direction) * r,
+                self.velocity + velocity,
+                body_mass,
+                self.body_radius,
+            ));
+        }
+        if self.central_mass > 0.0 {
+            bodies.push(Body::new(self.center, self.velocity, self.central_mass, self.central_radius));
+        }
+        bodies
+    }
+
+    /// Rotates a vector from the xy plane into the plane of the disk.
+    #[cfg(not(feature = "dim3"))]
+    fn tilt(&self, v: Vector) -> Vector {
+        v
+    }
+    #[cfg(feature = "dim3")]
+    fn tilt(&self, v: Vector) -> Vector {
+        v.rotate_x(self.inclination)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        check_positive(self.disk_mass, "scenario.disk_mass")?;
+        check_positive(self.body_radius, "scenario.body_radius")?;
+        check_positive(self.scale_length, "scenario.scale_length")?;
+        config::check(config::non_negative(self.central_mass), "scenario.central_mass", "must be non-negative")?;
+        config::check(config::non_negative(self.central_radius), "scenario.central_radius", "must be non-negative")?;
+        config::check(config::non_negative(self.dispersion), "scenario.dispersion", "must be non-negative")
+    }
+}
+
+/// Two identical disk galaxies approaching each other on offset paths.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct GalaxyCollision {
+    /// Parameters of both galaxies (their center and velocity are overridden).
+    pub galaxy: ExponentialDisk,
+    /// Initial distance between the galaxies along the x axis.
+    pub separation: Scalar,
+    /// Offset between the paths of the galaxies along the y axis (0 for a head-on collision).
+    pub impact_parameter: Scalar,
+    pub relative_speed: Scalar,
+    /// Inclination of the second disk relative to the first, in radians.
+    #[cfg(feature = "dim3")]
+    pub inclination: Scalar,
+}
+
+impl Default for GalaxyCollision {
+    fn default() -> Self {
+        GalaxyCollision {
+            galaxy: ExponentialDisk { num_bodies: 600, ..ExponentialDisk::default() },
+            separation: 800.0,
+            impact_parameter: 200.0,
+            relative_speed: 40.0,
+            #[cfg(feature = "dim3")]
+            inclination: 0.6,
+        }
+    }
+}
+
+impl GalaxyCollision {
+    fn generate(&self, gravity: &Gravity, rng: &mut impl Rng) -> Vec<Body> {
+        let offset = Vector::planar(self.separation, self.impact_parameter) / 2.0;
+        let velocity = Vector::planar(self.relative_speed / 2.0, 0.0);
+        let first = ExponentialDisk { center: offset * -1.0, velocity, ..self.galaxy };
+        let second = ExponentialDisk {
+            center: offset,
+            velocity: velocity * -1.0,
+            #[cfg(feature = "dim3")]
+            inclination: self.galaxy.inclination + self.inclination,
+            ..self.galaxy
+        };
+
+        let mut bodies = first.generate(gravity, rng);
+        bodies.extend(second.generate(gravity, rng));
+        bodies
+    }
+}
+
+/// A Plummer model star cluster (projected onto the plane in 2D), with isotropic random velocities
+/// scaled to a given virial ratio.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct PlummerSphere {
+    pub num_bodies: usize,
+    pub total_mass: Scalar,
+    /// Plummer radius, half the mass lies within about 1.3 times it.
+    pub scale_radius: Scalar,
+    pub body_radius: Scalar,
+    /// 2K / |W| of the initial state, 1 for equilibrium.
+    pub virial_ratio: Scalar,
+}
+
+impl Default for PlummerSphere {
+    fn default() -> Self {
+        PlummerSphere {
+            num_bodies: 1000,
+            total_mass: 5000.0,
+            scale_radius: 80.0,
+            body_radius: 0.5,
+            virial_ratio: 1.0,
+        }
+    }
+}
+
+impl PlummerSphere {
+    fn generate(&self, gravity: &Gravity, rng: &mut impl Rng) -> Vec<Body> {
+        let body_mass = self.total_mass / self.num_bodies.max(1) as Scalar;
+        let a = self.scale_radius;
+
+        let mut bodies: Vec<Body> = (0..self.num_bodies)
+            .map(|_| {
+                // cut off at 99% of the mass
+                let r = self.radius_enclosing(rng.random::<Scalar>() * 0.99);
+                // local velocity dispersion of the Plummer potential, rescaled below
+                let sigma = (gravity.grav_constant * self.total_mass / (6.0 * (r * r + a * a).sqrt())).sqrt();
+                Body::new(random_direction(rng) * r, gaussian(rng) * sigma, body_mass, self.body_radius)
+            })
+            .collect();
+        set_virial_ratio(&mut bodies, gravity, self.virial_ratio);
+        bodies
+    }
+
+    /// Radius enclosing the mass fraction `u`, inverting the projected enclosed mass R² / (R² + a²).
+    #[cfg(not(feature = "dim3"))]
+    fn radius_enclosing(&self, u: Scalar) -> Scalar {
+        self.scale_radius * (u / (1.0 - u)).sqrt()
+    }
+    /// Radius enclosing the mass fraction `u`, inverting the enclosed mass r³ / (r² + a²)^(3/2).
+    #[cfg(feature = "dim3")]
+    fn radius_enclosing(&self, u: Scalar) -> Scalar {
+        self.scale_radius / (u.powf(-2.0 / 3.0) - 1.0).sqrt()
+    }
+}
+
+/// A star with planets on circular orbits and a ring of small particles, all in the xy plane.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct PlanetarySystem {
+    pub star_mass: Scalar,
+    pub star_radius: Scalar,
+    /// Pin the star in place as a static body.
+    pub static_star: bool,
+    pub num_planets: usize,
+    pub planet_mass: Scalar,
+    pub planet_radius: Scalar,
+    /// Orbital radius of the innermost planet.
+    pub inner_orbit: Scalar,
+    /// Ratio between the orbital radii of consecutive planets.
+    pub orbit_spacing: Scalar,
+    pub ring_particles: usize,
+    /// Total mass of the ring.
+    pub ring_mass: Scalar,
+    pub ring_particle_radius: Scalar,
+    pub ring_inner_radius: Scalar,
+    pub ring_outer_radius: Scalar,
+}
+
+impl Default for PlanetarySystem {
+    fn default() -> Self {
+        PlanetarySystem {
+            star_mass: 10000.0,
+            star_radius: 10.0,
+            static_star: false,
+            num_planets: 4,
+            planet_mass: 5.0,
+            planet_radius: 2.0,
+            inner_orbit: 60.0,
+            orbit_spacing: 1.6,
+            ring_particles: 400,
+            ring_mass: 1.0,
+            ring_particle_radius: 0.3,
+            ring_inner_radius: 180.0,
+            ring_outer_radius: 210.0,
+        }
+    }
+}
+
+impl PlanetarySystem {
+    fn generate(&self, gravity: &Gravity, rng: &mut impl Rng) -> Vec<Body> {
+        let mut star = Body::new(Vector::zero(), Vector::zero(), self.star_mass, self.star_radius);
+        if self.static_star {
+            star.set_kind(BodyKind::Static);
+        }
+        let mut bodies = vec![star];
+
+        // circular orbit around the star, ignoring the pull of the other bodies
+        let orbit = |direction: Vector, radius: Scalar, mass: Scalar, body_radius: Scalar| {
+            let speed = (gravity.grav_constant * self.star_mass / radius).sqrt();
+            Body::new(direction * radius, direction.perp() * speed, mass, body_radius)
+        };
+
+        let mut radius = self.inner_orbit;
+        for _ in 0..self.num_planets {
+            bodies.push(orbit(random_planar_direction(rng), radius, self.planet_mass, self.planet_radius));
+            radius *= self.orbit_spacing;
+        }
+
+        let particle_mass = self.ring_mass / self.ring_particles.max(1) as Scalar;
+        for _ in 0..self.ring_particles {
+            let radius = self.ring_inner_radius + rng.random::<Scalar>() * (self.ring_outer_radius - self.ring_inner_radius);
+            bodies.push(orbit(random_planar_direction(rng), radius, particle_mass, self.ring_particle_radius));
+        }
+        bodies
+    }
+}
+
+/// A uniform disk (ball in 3D) of bodies nearly at rest, collapsing under its own gravity.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ColdCollapse {
+    pub num_bodies: usize,
+    pub total_mass: Scalar,
+    pub radius: Scalar,
+    pub body_radius: Scalar,
+    /// 2K / |W| of the initial random motions, 0 starts at rest.
+    pub virial_ratio: Scalar,
+}
+
+impl Default for ColdCollapse {
+    fn default() -> Self {
+        ColdCollapse {
+            num_bodies: 1000,
+            total_mass: 5000.0,
+            radius: 200.0,
+            body_radius: 0.5,
+            virial_ratio: 0.05,
+        }
+    }
+}
+
+impl ColdCollapse {
+    fn generate(&self, gravity: &Gravity, rng: &mut impl Rng) -> Vec<Body> {
+        let body_mass = self.total_mass / self.num_bodies.max(1) as Scalar;
+        let mut bodies: Vec<Body> = (0..self.num_bodies)
+            .map(|_| {
+                // the enclosed fraction of a uniform disk grows as r² (r³ for a ball)
+                #[cfg(not(feature = "dim3"))]
+                let r = self.radius * rng.random::<Scalar>().sqrt();
+                #[cfg(feature = "dim3")]
+                let r = self.radius * rng.random::<Scalar>().cbrt();
+                Body::new(random_direction(rng) * r, gaussian(rng), body_mass, self.body_radius)
+            })
+            .collect();
+        set_virial_ratio(&mut bodies, gravity, self.virial_ratio);
+        bodies
+    }
+}
+
+/// A column of grains dropped into the middle of a box under uniform gravity, spreading into a
+/// pile. Grains don't attract each other.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct GranularPile {
+    pub num_grains: usize,
+    pub grain_mass: Scalar,
+    pub grain_radius: Scalar,
+    /// Width of the column the grains start in.
+    pub column_width: Scalar,
+    /// Width of the box, with its floor at y = 0.
+    pub width: Scalar,
+    /// Downward acceleration.
+    pub gravity: Scalar,
+}
+
+impl Default for GranularPile {
+    fn default() -> Self {
+        GranularPile {
+            num_grains: 600,
+            grain_mass: 1.0,
+            grain_radius: 1.0,
+            // narrower in 3D, where every layer of the column is a square of grains
+            #[cfg(not(feature = "dim3"))]
+            column_width: 40.0,
+            #[cfg(feature = "dim3")]
+            column_width: 12.0,
+            width: 200.0,
+            gravity: 200.0,
+        }
+    }
+}
+
+impl GranularPile {
+    /// Grains are placed on a loose grid, with some jitter so the column doesn't stack perfectly.
+    fn spacing(&self) -> Scalar {
+        2.2 * self.grain_radius
+    }
+
+    /// Grains across the column along each horizontal axis.
+    fn columns(&self) -> usize {
+        ((self.column_width / self.spacing()).floor() as usize).max(1)
+    }
+
+    /// Grains in each horizontal layer (a row in 2D, a square in 3D).
+    fn grains_per_layer(&self) -> usize {
+        self.columns().pow(Vector::DIM as u32 - 1)
+    }
+
+    /// The box container, tall enough to hold the initial column.
+    pub fn container(&self) -> Boundary {
+        let rows = self.num_grains.div_ceil(self.grains_per_layer());
+        let height = (rows as Scalar * self.spacing() + self.spacing()).max(self.width);
+        let (mut min, mut max) = (Vector::splat(-self.width / 2.0), Vector::splat(self.width / 2.0));
+        min.y = 0.0;
+        max.y = height;
+        Boundary::Box { min, max, material: 0 }
+    }
+
+    fn generate(&self, rng: &mut impl Rng) -> Vec<Body> {
+        let spacing = self.spacing();
+        let columns = self.columns();
+        let left = -(columns as Scalar - 1.0) * spacing / 2.0;
+        (0..self.num_grains)
+            .map(|k| {
+                let (row, mut column) = (k / self.grains_per_layer(), k % self.grains_per_layer());
+                let mut axes = [0.0; Vector::DIM];
+                axes[1] = (row as Scalar + 0.5) * spacing;
+                // horizontal axes (x, then z in 3D) take the digits of the index within the layer
+                for axis in (0..Vector::DIM).filter(|&axis| axis != 1) {
+                    let jitter = (rng.random::<Scalar>() - 0.5) * 0.1 * self.grain_radius;
+                    axes[axis] = left + (column % columns) as Scalar * spacing + jitter;
+                    column /= columns;
+                }
+                Body::new(Vector::from_axes(axes), Vector::zero(), self.grain_mass, self.grain_radius)
+            })
+            .collect()
+    }
+}
+
+/// Uniform random number in (0, 1], safe to take the logarithm of.
+fn unit_open(rng: &mut impl Rng) -> Scalar {
+    1.0 - rng.random::<Scalar>()
+}
+
+/// Uniformly random unit vector in the xy plane.
+fn random_planar_direction(rng: &mut impl Rng) -> Vector {
+    let angle = rng.random::<Scalar>() * 2.0 * PI;
+    Vector::planar(angle.cos(), angle.sin())
+}
+
+/// Uniformly random unit vector on the circle (sphere in 3D).
+#[cfg(not(feature = "dim3"))]
+fn random_direction(rng: &mut impl Rng) -> Vector {
+    random_planar_direction(rng)
+}
+#[cfg(feature = "dim3")]
+fn random_direction(rng: &mut impl Rng) -> Vector {
+    // z is uniform on the unit sphere (Archimedes' hat-box theorem)
+    let z = 2.0 * rng.random::<Scalar>() - 1.0;
+    random_planar_direction(rng) * (1.0 - z * z).sqrt() + Vector::new(0.0, 0.0, z)
+}
+
+/// Vector of independent standard normal samples (Box-Muller transform).
+#[cfg(not(feature = "dim3"))]
+fn gaussian(rng: &mut impl Rng) -> Vector {
+    random_direction(rng) * (-2.0 * unit_open(rng).ln()).sqrt()
+}
+#[cfg(feature = "dim3")]
+fn gaussian(rng: &mut impl Rng) -> Vector {
+    // a Box-Muller pair in the plane, and one sample of another pair along z
+    let z = (-2.0 * unit_open(rng).ln()).sqrt() * (rng.random::<Scalar>() * 2.0 * PI).cos();
+    random_planar_direction(rng) * (-2.0 * unit_open(rng).ln()).sqrt() + Vector::new(0.0, 0.0, z)
+}
+
+/// Removes the net momentum of `bodies` and rescales their velocities so that 2K / |W| equals
+/// `ratio`. Bodies without velocities are left at rest.
+fn set_virial_ratio(bodies: &mut [Body], gravity: &Gravity, ratio: Scalar) {
+    let total_mass: Scalar = bodies.iter().map(|b| b.mass).sum();
+    if total_mass <= 0.0 {
+        return;
+    }
+    let mean_velocity = bodies.iter().fold(Vector::zero(), |acc, b| acc + b.velocity * b.mass) / total_mass;
+    for body in bodies.iter_mut() {
+        body.velocity -= mean_velocity;
+    }
+
+    let kinetic_energy: Scalar = bodies.iter().map(|b| 0.5 * b.mass * b.velocity.length_squared()).sum();
+    let potential_energy = gravity.potential_energy(bodies, &Boundary::Open);
+    if kinetic_energy <= 0.0 {
+        return;
+    }
+    let scale = (ratio * potential_energy.abs() / (2.0 * kinetic_energy)).sqrt();
+    for body in bodies.iter_mut() {
+        body.velocity *= scale;
+    }
+}
@@ -0,0 +1,140 @@
+use std::ops::{Add, Div, Mul, Sub};
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+use std::arch::wasm32::*;
+
+/// Four f32 lanes, backed by SSE2 on x86_64, simd128 on wasm (when built with
+/// `-C target-feature=+simd128`) and a plain array elsewhere.
+#[derive(Clone, Copy, Debug)]
+pub struct F32x4(Lanes);
+
+#[cfg(target_arch = "x86_64")]
+type Lanes = __m128;
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+type Lanes = v128;
+#[cfg(not(any(target_arch = "x86_64", all(target_arch = "wasm32", target_feature = "simd128"))))]
+type Lanes = [f32; 4];
+
+// SAFETY: the SSE intrinsics below only need SSE and SSE2, which every x86_64 cpu has
+#[cfg(target_arch = "x86_64")]
+impl F32x4 {
+    pub fn splat(value: f32) -> Self {
+        F32x4(unsafe { _mm_set1_ps(value) })
+    }
+
+    /// Loads the first four values of `values`.
+    pub fn load(values: &[f32; 4]) -> Self {
+        // SAFETY: reads exactly the four values of the array, without alignment requirement
+        F32x4(unsafe { _mm_loadu_ps(values.as_ptr()) })
+    }
+
+    pub fn sqrt(self) -> Self {
+        F32x4(unsafe { _mm_sqrt_ps(self.0) })
+    }
+
+    /// Keeps the lanes where `mask` is greater than 0 and zeroes the others.
+    pub fn where_positive(self, mask: Self) -> Self {
+        F32x4(unsafe { _mm_and_ps(self.0, _mm_cmpgt_ps(mask.0, _mm_setzero_ps())) })
+    }
+
+    pub fn to_array(self) -> [f32; 4] {
+        let mut values = [0.0; 4];
+        // SAFETY: writes exactly the four values of the array, without alignment requirement
+        unsafe { _mm_storeu_ps(values.as_mut_ptr(), self.0) };
+        values
+    }
+}
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+impl F32x4 {
+    pub fn splat(value: f32) -> Self {
+        F32x4(f32x4_splat(value))
+    }
+
+    /// Loads the first four values of `values`.
+    pub fn load(values: &[f32; 4]) -> Self {
+        F32x4(f32x4(values[0], values[1], values[2], values[3]))
+    }
+
+    pub fn sqrt(self) -> Self {
+        F32x4(f32x4_sqrt(self.0))
+    }
+
+    /// Keeps the lanes where `mask` is greater than 0 and zeroes the others.
+    pub fn where_positive(self, mask: Self) -> Self {
+        F32x4(v128_and(self.0, f32x4_gt(mask.0, f32x4_splat(0.0))))
+    }
+
+    pub fn to_array(self) -> [f32; 4] {
+        [
+            f32x4_extract_lane::<0>(self.0),
+            f32x4_extract_lane::<1>(self.0),
+            f32x4_extract_lane::<2>(self.0),
+            f32x4_extract_lane::<3>(self.0),
+        ]
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", all(target_arch = "wasm32", target_feature = "simd128"))))]
+impl F32x4 {
+    pub fn splat(value: f32) -> Self {
+        F32x4([value; 4])
+    }
+
+    /// Loads the first four values of `values`.
+    pub fn load(values: &[f32; 4]) -> Self {
+        F32x4(*values)
+    }
+
+    pub fn sqrt(self) -> Self {
+        F32x4(self.0.map(f32::sqrt))
+    }
+
+    /// Keeps the lanes where `mask` is greater than 0 and zeroes the others.
+    pub fn where_positive(self, mask: Self) -> Self {
+        F32x4(std::array::from_fn(|i| if mask.0[i] > 0.0 { self.0[i] } else { 0.0 }))
+    }
+
+    pub fn to_array(self) -> [f32; 4] {
+        self.0
+    }
+}
+
+impl F32x4 {
+    /// Sum of the four lanes, in lane order.
+    pub fn sum(self) -> f32 {
+        self.to_array().iter().sum()
+    }
+}
+
+/// Implements a lane-wise arithmetic operator with the SSE2 and simd128 intrinsic for it.
+macro_rules! lane_op {
+    ($trait:ident, $method:ident, $sse:ident, $simd128:ident, $op:tt) => {
+        impl $trait for F32x4 {
+            type Output = Self;
+
+            #[cfg(target_arch = "x86_64")]
+            fn $method(self, other: Self) -> Self {
+                // SAFETY: SSE is always available on x86_64
+                F32x4(unsafe { $sse(self.0, other.0) })
+            }
+
+            #[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+            fn $method(self, other: Self) -> Self {
+                F32x4($simd128(self.0, other.0))
+            }
+
+            #[cfg(not(any(target_arch = "x86_64", all(target_arch = "wasm32", target_feature = "simd128"))))]
+            fn $method(self, other: Self) -> Self {
+                F32x4(std::array::from_fn(|i| self.0[i] $op other.0[i]))
+            }
+        }
+    };
+}
+
+lane_op!(Add, add, _mm_add_ps, f32x4_add, +);
+lane_op!(Sub, sub, _mm_sub_ps, f32x4_sub, -);
+lane_op!(Mul, mul, _mm_mul_ps, f32x4_mul, *);
+lane_op!(Div, div, _mm_div_ps, f32x4_div, /);
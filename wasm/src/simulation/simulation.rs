@@ -1,118 +1,672 @@
-use crate::simulation::body::{self, Body};
-use crate::simulation::vec2::Vec2;
+use std::cell::{OnceCell, RefCell};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::simulation::body::{Body, BodyId, BodyKind};
+use crate::simulation::boundary::Boundary;
+use crate::simulation::broadphase::{self, Broadphase};
+use crate::simulation::ccd;
+use crate::simulation::collision::{self, CollisionMode, Fragmentation};
+use crate::simulation::config::{self, ConfigError, SimulationConfig};
+use crate::simulation::contact_solver::{ContactSolver, ContactSolverSettings};
+use crate::simulation::diagnostics::Diagnostics;
+use crate::simulation::force_field::{self, ForceField};
+use crate::simulation::force_law::{self, ForceLaw, ForceLawKind};
+use crate::simulation::gravity::{self, Gravity, GravitySolver};
+use crate::simulation::integrator::Integrator;
+use crate::simulation::material::{Material, PairMaterial};
+use crate::simulation::soa::BodyArrays;
+use crate::simulation::softening::Softening;
+use crate::simulation::spatial_index::SpatialIndex;
+use crate::simulation::vec2::{Scalar, Vector};
 
 /// The main simulation struct, containing bodies and simulation parameters and responsible 
 /// for updating the simulation state.
 pub struct Simulation {
-    grav_constant: f32,
-    coeff_restitution: f32,
+    gravity: Gravity,
+    force_fields: Vec<ForceField>,
+    force_laws: Vec<Box<dyn ForceLaw>>,
+    materials: Vec<Material>,
+    boundary: Boundary,
+    collision_mode: CollisionMode,
+    contact_solver: ContactSolver,
+    broadphase: Broadphase,
+    continuous_collisions: bool,
+    integrator: Box<dyn Integrator>,
     bodies: Vec<Body>,
+    // positions and masses of `bodies` as a structure of arrays, read by the vectorized gravity
+    // kernel. Kept across steps so its arrays are reused, synced before every force evaluation and,
+    // when stale, before being handed out
+    body_arrays: BodyArrays,
+    body_arrays_stale: bool,
+    // id given to the next added body, ids are never reused
+    next_id: u32,
+    // index of the current body positions for spatial queries, built by the first query and
+    // dropped whenever bodies move, are added or removed
+    spatial_index: OnceCell<SpatialIndex>,
+    // seed of `rng`, which drives all randomness (initial conditions and fragmentation) so runs
+    // can be reproduced
+    seed: u64,
+    rng: StdRng,
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Simulation::new()
+    }
 }
 
 impl Simulation {
-    /// Creates a new simulation with default parameters and bodies.
+    /// Creates a new simulation with default parameters and bodies, from a random seed.
     pub fn new() -> Self {
-        let num_bodies = 500;
-        let mut bodies = vec![];
+        Simulation::with_seed(rand::random())
+    }
 
-        let radius = 500.0;
-        for _ in 0..num_bodies {
-            let mut pos = Vec2::new(rand::random(), rand::random());
-            pos = (pos - Vec2::new(0.5, 0.5)) * radius;
-            // let vel = Vec2::new(pos.y, -pos.x).normalize() * (1.0 / (pos.length() + 0.1).sqrt()) * 800.0;
-            let vel = Vec2::zero();
-            bodies.push(Body::new(
-                pos,
-                vel,
-                5.0,
-                1.0,
-            ));
-        }
+    /// Creates a new simulation with default parameters and bodies generated from `seed`. The same
+    /// seed and parameters give bit-identical runs on the same target.
+    pub fn with_seed(seed: u64) -> Self {
+        let config = SimulationConfig { seed: Some(seed), ..SimulationConfig::default() };
+        Simulation::from_config(&config).expect("default config is valid")
+    }
 
-        Simulation {
-            grav_constant: 600.0,
-            coeff_restitution: 0.95,
-            bodies,
+    /// Creates a new simulation from `config`, generating its initial bodies from the scenario.
+    pub fn from_config(config: &SimulationConfig) -> Result<Self, ConfigError> {
+        config.validate()?;
+        let seed = config.seed.unwrap_or_else(rand::random);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let gravity = Gravity {
+            grav_constant: config.grav_constant,
+            softening: config.softening,
+            solver: config.gravity_solver,
+        };
+        let bodies = config.scenario.generate(&gravity, &mut rng);
+
+        let mut simulation = Simulation {
+            gravity,
+            force_fields: config.force_fields.clone(),
+            force_laws: config.force_laws.iter().map(|law| law.build()).collect(),
+            materials: vec![config.material],
+            boundary: Boundary::Open,
+            collision_mode: config.collision_mode,
+            contact_solver: ContactSolver::new(config.contact_solver),
+            broadphase: config.broadphase,
+            continuous_collisions: config.continuous_collisions,
+            integrator: config.integrator.build(),
+            bodies: vec![],
+            body_arrays: BodyArrays::default(),
+            body_arrays_stale: true,
+            next_id: 1,
+            spatial_index: OnceCell::new(),
+            seed,
+            rng,
+        };
+        for body in bodies {
+            simulation.insert_body(body);
         }
+        simulation.set_boundary(config.boundary)?;
+        Ok(simulation)
     }
 
     /// Updates the simulation state by a time step `dt`.
-    pub fn update(&mut self, dt: f32) {
-
-        // update acceleration
-        for i in 0..self.bodies.len() {
-            let mut accel = Vec2::zero();
-            for j in 0..self.bodies.len() {
-                if i == j {
-                    continue;
+    pub fn update(&mut self, dt: Scalar) {
+        self.bodies_changed();
+
+        // update velocities and positions
+        let start_positions: Vec<Vector> = self.bodies.iter().map(|b| b.position).collect();
+        let (gravity, boundary, fields, laws) = (self.gravity, self.boundary, &self.force_fields, &self.force_laws);
+        let body_arrays = RefCell::new(std::mem::take(&mut self.body_arrays));
+        self.integrator.step(&mut self.bodies, dt, &|bodies| {
+            let mut arrays = body_arrays.borrow_mut();
+            arrays.sync(bodies);
+            let mut accelerations = gravity.accelerations_with(bodies, &boundary, &arrays);
+            force_law::add_accelerations(laws, bodies, &boundary, &mut accelerations);
+            force_field::add_accelerations(fields, bodies, &gravity, &boundary, &mut accelerations);
+            // static and kinematic bodies attract others but aren't accelerated themselves
+            for (accel, body) in accelerations.iter_mut().zip(bodies) {
+                if !body.is_dynamic() {
+                    *accel = Vector::zero();
                 }
-                let direction = self.bodies[j].position - self.bodies[i].position;
-                let distance_sq = direction.length_squared().max(0.001);
-                let f = self.grav_constant * self.bodies[j].mass / distance_sq;
-                accel += direction.normalize() * f;
             }
-            self.bodies[i].velocity += accel * dt;
-        }
+            accelerations
+        });
+        self.body_arrays = body_arrays.into_inner();
+
+        // catch contacts that happened during the step (fast bodies passing through each other)
+        let swept = if self.continuous_collisions {
+            self.resolve_swept_collisions(&start_positions, dt)
+        } else {
+            vec![]
+        };
+
+        // solve collisions, accreting bodies don't bounce off each other but still hit the walls
+        let pairs = broadphase::candidate_pairs(&self.bodies, self.broadphase, &self.boundary);
+        let contact_pairs = match self.collision_mode {
+            CollisionMode::Bounce => pairs,
+            CollisionMode::Merge => {
+                self.accrete_collisions(&pairs, &swept, None);
+                vec![]
+            }
+            CollisionMode::Fragment(params) => {
+                self.accrete_collisions(&pairs, &swept, Some(params));
+                vec![]
+            }
+        };
+        self.contact_solver.solve(&mut self.bodies, &contact_pairs, &self.boundary, &self.materials, dt);
 
-        // update positions
+        // bodies leaving a periodic domain re-enter on the other side
         for body in self.bodies.iter_mut() {
-            body.position += body.velocity * dt;
-        }
-
-        // solve collisions
-        for i in 0..self.bodies.len() {
-            for j in i+1..self.bodies.len() {
-
-                let direction = self.bodies[j].position - self.bodies[i].position;
-                let distance = direction.length();
-                let min_distance = self.bodies[i].radius + self.bodies[j].radius;
-                let constraint_distance = distance - min_distance;
-
-                if constraint_distance < 0.0 {
-                    let normal = direction.normalize();
-                    let relative_velocity = self.bodies[j].velocity - self.bodies[i].velocity;
-                    let constraint_velocity = relative_velocity.dot(normal);
-                    
-                    if constraint_velocity < 0.0 {
-                        let body_i_mass = self.bodies[i].mass;
-                        let body_j_mass = self.bodies[j].mass;
-                        let denom = 1.0 / body_i_mass + 1.0 / body_j_mass;
-                        
-                        // velocity correction (impulse)
-                        let impulse = (1.0 + self.coeff_restitution) * constraint_velocity / denom;
-                        let impulse_vec = normal * impulse;
-                        self.bodies[i].velocity += impulse_vec / body_i_mass;
-                        self.bodies[j].velocity -= impulse_vec / body_j_mass;
-                    }
-                    
-                    // position correction
-                    let correction_amount = constraint_distance.abs() * 1.0; // 80% correction
-                    let correction = normal * correction_amount;
-                    let total_mass = self.bodies[i].mass + self.bodies[j].mass;
-                    let body_i_mass = self.bodies[i].mass;
-                    let body_j_mass = self.bodies[j].mass;
-                    self.bodies[i].position -= correction * (body_j_mass / total_mass);
-                    self.bodies[j].position += correction * (body_i_mass / total_mass);
-
-                    // tangent velocity correction (friction-like)
-                    let tangent_velocity = relative_velocity - normal * constraint_velocity;
-                    let tangent_speed = tangent_velocity.length();
-                    if tangent_speed > 0.0 {
-                        let friction_impulse_magnitude = tangent_speed * 0.5; // friction coefficient
-                        let friction_impulse = tangent_velocity.normalize() * friction_impulse_magnitude;
-                        let body_i_mass = self.bodies[i].mass;
-                        let body_j_mass = self.bodies[j].mass;
-                        self.bodies[i].velocity += friction_impulse / body_i_mass;
-                        self.bodies[j].velocity -= friction_impulse / body_j_mass;
-                    }
-                
+            body.position = self.boundary.wrap(body.position);
+        }
+    }
+
+    /// Resolves contacts found by sweeping bodies from `start` to their current positions, at the
+    /// time of impact. Each body takes part in at most one swept contact per step (the earliest).
+    ///
+    /// In bounce mode, the pair is rewound to the moment of contact, bounced, and moved for the rest
+    /// of the step. Otherwise the pair is left touching and returned (sorted), so the accretion pass
+    /// can merge or fragment it.
+    fn resolve_swept_collisions(&mut self, start: &[Vector], dt: Scalar) -> Vec<(usize, usize)> {
+        let mut contacts = ccd::swept_contacts(start, &self.bodies, self.broadphase, &self.boundary);
+        contacts.sort_by(|a, b| a.time_of_impact.total_cmp(&b.time_of_impact));
+
+        let mut handled = vec![false; self.bodies.len()];
+        let mut hits = vec![];
+        for contact in contacts {
+            let (i, j, toi) = (contact.i, contact.j, contact.time_of_impact);
+            if handled[i] || handled[j] {
+                continue;
+            }
+            handled[i] = true;
+            handled[j] = true;
+
+            // rewind to the time of impact
+            for k in [i, j] {
+                let body = &mut self.bodies[k];
+                body.position = start[k] + (body.position - start[k]) * toi;
+            }
+
+            if self.collision_mode == CollisionMode::Bounce {
+                let normal = self.boundary.displacement(self.bodies[i].position, self.bodies[j].position).normalize();
+                self.apply_restitution_impulse(i, j, normal);
+                for k in [i, j] {
+                    let body = &mut self.bodies[k];
+                    body.position += body.velocity * ((1.0 - toi) * dt);
+                }
+            }
+            hits.push((i, j));
+        }
+        hits.sort_unstable();
+        hits
+    }
+
+    /// Merges or fragments every overlapping candidate pair, removing the original bodies and adding
+    /// any fragments at the end with new ids. A body can absorb several others in one pass, always
+    /// keeping the lower index (and the id of the heavier body).
+    ///
+    /// Only pairs overlapping at the start of the pass combine: the candidates were found before any
+    /// merge, so overlaps created by a body growing are left to the next step, whatever the
+    /// broadphase.
+    ///
+    /// `swept` pairs (sorted) are in contact even if not overlapping, see `resolve_swept_collisions`.
+    fn accrete_collisions(&mut self, pairs: &[(usize, usize)], swept: &[(usize, usize)], fragmentation: Option<Fragmentation>) {
+        let mut all_pairs = [pairs, swept].concat();
+        all_pairs.sort_unstable();
+        all_pairs.dedup();
+
+        let start = self.bodies.clone();
+
+        let mut removed = vec![false; self.bodies.len()];
+        let mut fragments = vec![];
+        for (i, j) in all_pairs {
+            if removed[i] || removed[j] {
+                continue;
+            }
+            let overlapped = start[i].overlaps(&self.boundary.nearest_image(&start[j], start[i].position));
+            if !overlapped && swept.binary_search(&(i, j)).is_err() {
+                continue;
+            }
+            // the image of j next to i, so pairs touching across periodic edges combine in place
+            let other = self.boundary.nearest_image(&self.bodies[j], self.bodies[i].position);
+            let shattered = fragmentation
+                .and_then(|params| collision::fragment(&self.bodies[i], &other, &params, &mut self.rng));
+            match shattered {
+                Some(new_bodies) => {
+                    fragments.extend(new_bodies);
+                    removed[i] = true;
                 }
+                None => self.bodies[i] = collision::merge(&self.bodies[i], &other),
             }
+            removed[j] = true;
+        }
+
+        let mut removed = removed.into_iter();
+        self.bodies.retain(|_| !removed.next().unwrap());
+        for fragment in fragments {
+            self.insert_body(fragment);
+        }
+    }
+
+    /// Applies the restitution impulse along `normal` (pointing from `i` to `j`) if the bodies are
+    /// approaching each other.
+    fn apply_restitution_impulse(&mut self, i: usize, j: usize, normal: Vector) {
+        let relative_velocity = self.bodies[j].velocity - self.bodies[i].velocity;
+        let constraint_velocity = relative_velocity.dot(normal);
+
+        let inv_mass_i = self.bodies[i].inverse_mass();
+        let inv_mass_j = self.bodies[j].inverse_mass();
+        let denom = inv_mass_i + inv_mass_j;
+
+        if constraint_velocity < 0.0 && denom > 0.0 {
+            let restitution = PairMaterial::of_bodies(&self.materials, &self.bodies[i], &self.bodies[j]).restitution;
+            let impulse = (1.0 + restitution) * constraint_velocity / denom;
+            let impulse_vec = normal * impulse;
+            self.bodies[i].velocity += impulse_vec * inv_mass_i;
+            self.bodies[j].velocity -= impulse_vec * inv_mass_j;
+        }
+    }
+
+    /// Sets the algorithm used to compute gravitational accelerations. Fails if its opening angle
+    /// is negative or not finite.
+    pub fn set_gravity_solver(&mut self, solver: GravitySolver) -> Result<(), ConfigError> {
+        solver.validate()?;
+        self.gravity.solver = solver;
+        Ok(())
+    }
+
+    /// Sets the gravitational softening kernel, used by every gravity solver. Fails if its length
+    /// is negative or not finite.
+    pub fn set_softening(&mut self, softening: Softening) -> Result<(), ConfigError> {
+        softening.validate()?;
+        self.gravity.softening = softening;
+        Ok(())
+    }
+
+    /// Adds an external force field acting on every body. Fails if its parameters are out of range.
+    pub fn add_force_field(&mut self, field: ForceField) -> Result<(), ConfigError> {
+        field.validate()?;
+        self.force_fields.push(field);
+        Ok(())
+    }
+
+    /// Removes all external force fields.
+    pub fn clear_force_fields(&mut self) {
+        self.force_fields.clear();
+    }
+
+    /// Adds a pairwise force law acting between bodies on top of gravity. Fails if its parameters
+    /// are out of range.
+    pub fn add_force_law(&mut self, law: ForceLawKind) -> Result<(), ConfigError> {
+        law.validate()?;
+        self.force_laws.push(law.build());
+        Ok(())
+    }
+
+    /// Removes all force laws other than gravity.
+    pub fn clear_force_laws(&mut self) {
+        self.force_laws.clear();
+    }
+
+    /// Sets the time integration scheme used to advance bodies.
+    pub fn set_integrator(&mut self, integrator: Box<dyn Integrator>) {
+        self.integrator = integrator;
+    }
+
+    /// Sets how overlapping bodies are resolved. Fails if fragmentation parameters are out of range.
+    pub fn set_collision_mode(&mut self, mode: CollisionMode) -> Result<(), ConfigError> {
+        mode.validate()?;
+        self.collision_mode = mode;
+        Ok(())
+    }
+
+    /// Enables or disables continuous (swept) collision detection, which catches fast bodies that
+    /// would otherwise tunnel through each other within a single step.
+    pub fn set_continuous_collisions(&mut self, enabled: bool) {
+        self.continuous_collisions = enabled;
+    }
+
+    /// Sets the container bodies live in. Bodies are wrapped into a periodic domain right away.
+    /// Fails, leaving the container unchanged, if it is empty or inverted.
+    pub fn set_boundary(&mut self, boundary: Boundary) -> Result<(), ConfigError> {
+        boundary.validate()?;
+        let known_material = boundary.material().is_none_or(|m| (m as usize) < self.materials.len());
+        config::check(known_material, "boundary.material", "no material at this index")?;
+        self.boundary = boundary;
+        for body in self.bodies.iter_mut() {
+            body.position = boundary.wrap(body.position);
+        }
+        self.contact_solver.clear_cache();
+        self.bodies_changed();
+        Ok(())
+    }
+
+    /// Returns the container bodies live in.
+    pub fn get_boundary(&self) -> &Boundary {
+        &self.boundary
+    }
+
+    /// Adds a material and returns its index, to be assigned to bodies with `set_body_material`.
+    pub fn add_material(&mut self, material: Material) -> Result<u32, ConfigError> {
+        material.validate()?;
+        self.materials.push(material);
+        Ok((self.materials.len() - 1) as u32)
+    }
+
+    /// Replaces the material at `index` (0 is the default material of new bodies and walls).
+    pub fn set_material(&mut self, index: u32, material: Material) -> Result<(), ConfigError> {
+        material.validate()?;
+        config::check((index as usize) < self.materials.len(), "material", "no material at this index")?;
+        self.materials[index as usize] = material;
+        Ok(())
+    }
+
+    /// Adds a body (wrapped into a periodic domain) and returns its newly assigned id. Fails if
+    /// the body has a non-finite state, negative mass or radius, or an unknown material.
+    pub fn add_body(&mut self, body: Body) -> Result<BodyId, ConfigError> {
+        body.validate()?;
+        config::check((body.material as usize) < self.materials.len(), "body.material", "no material at this index")?;
+        Ok(self.insert_body(body))
+    }
+
+    /// Adds a body known to be valid, e.g. generated by a scenario or a collision.
+    fn insert_body(&mut self, mut body: Body) -> BodyId {
+        body.id = BodyId(self.next_id);
+        self.next_id += 1;
+        body.position = self.boundary.wrap(body.position);
+        body.set_kind(body.kind); // stops static bodies given a velocity
+        self.bodies.push(body);
+        self.bodies_changed();
+        body.id
+    }
+
+    /// Removes the body with id `id` and returns it, or None if there is no such body (e.g. it was
+    /// already removed or absorbed by a merge).
+    pub fn remove_body(&mut self, id: BodyId) -> Option<Body> {
+        let index = self.bodies.iter().position(|b| b.id == id)?;
+        self.bodies_changed();
+        Some(self.bodies.remove(index))
+    }
+
+    /// Returns the body with id `id`, if it is still in the simulation.
+    pub fn get_body(&self, id: BodyId) -> Option<&Body> {
+        self.bodies.iter().find(|b| b.id == id)
+    }
+
+    /// Positions and masses of all bodies (in the order of `get_bodies`) as a structure of arrays,
+    /// padded with massless bodies.
+    pub fn body_arrays(&mut self) -> &BodyArrays {
+        if self.body_arrays_stale {
+            self.body_arrays.sync(&self.bodies);
+            self.body_arrays_stale = false;
+        }
+        &self.body_arrays
+    }
+
+    /// Drops the state derived from the bodies, after they moved or were added or removed.
+    fn bodies_changed(&mut self) {
+        self.spatial_index.take();
+        self.body_arrays_stale = true;
+    }
+
+    fn spatial_index(&self) -> &SpatialIndex {
+        self.spatial_index.get_or_init(|| SpatialIndex::new(&self.bodies))
+    }
+
+    fn ids(&self, indices: Vec<usize>) -> Vec<BodyId> {
+        indices.into_iter().map(|i| self.bodies[i].id).collect()
+    }
+
+    /// Returns the body whose disc (sphere in 3D) contains `point`, the one with the closest center
+    /// if several do.
+    pub fn body_at(&self, point: Vector) -> Option<BodyId> {
+        let index = self.spatial_index().body_at(&self.bodies, &self.boundary, point)?;
+        Some(self.bodies[index].id)
+    }
+
+    /// Returns the bodies with centers within `radius` of `center`.
+    pub fn bodies_within(&self, center: Vector, radius: Scalar) -> Vec<BodyId> {
+        self.ids(self.spatial_index().within(&self.bodies, &self.boundary, center, radius))
+    }
+
+    /// Returns the `k` bodies with centers nearest to `point`, closest first.
+    pub fn nearest_bodies(&self, point: Vector, k: usize) -> Vec<BodyId> {
+        self.ids(self.spatial_index().nearest(&self.bodies, &self.boundary, point, k))
+    }
+
+    /// Returns the bodies with centers inside the axis-aligned box from `min` to `max`.
+    pub fn bodies_in_rect(&self, min: Vector, max: Vector) -> Vec<BodyId> {
+        self.ids(self.spatial_index().in_rect(&self.bodies, &self.boundary, min, max))
+    }
+
+    /// Returns the bodies with centers inside every half-space `normal · p >= offset` of `planes`,
+    /// e.g. the frustum of a selection rectangle in a perspective view. Periodic images are not
+    /// found.
+    pub fn bodies_in_planes(&self, planes: &[(Vector, Scalar)]) -> Vec<BodyId> {
+        self.ids(self.spatial_index().in_planes(&self.bodies, planes))
+    }
+
+    /// Returns the first body hit by the ray from `origin` along the unit vector `direction`, e.g.
+    /// to pick the body under the cursor in a perspective view. Periodic images are not hit.
+    pub fn body_on_ray(&self, origin: Vector, direction: Vector) -> Option<BodyId> {
+        let index = self.spatial_index().first_hit(&self.bodies, origin, direction)?;
+        Some(self.bodies[index].id)
+    }
+
+    fn get_body_mut(&mut self, id: BodyId) -> Option<&mut Body> {
+        self.body_arrays_stale = true;
+        self.bodies.iter_mut().find(|b| b.id == id)
+    }
+
+    /// Assigns material `material` to the body with id `id`. Fails if there is no such material.
+    pub fn set_body_material(&mut self, id: BodyId, material: u32) -> Result<(), ConfigError> {
+        config::check((material as usize) < self.materials.len(), "body.material", "no material at this index")?;
+        if let Some(body) = self.get_body_mut(id) {
+            body.material = material;
+        }
+        Ok(())
+    }
+
+    /// Sets the electric charge of the body with id `id`.
+    pub fn set_body_charge(&mut self, id: BodyId, charge: Scalar) {
+        if let Some(body) = self.get_body_mut(id) {
+            body.charge = charge;
+        }
+    }
+
+    /// Makes the body with id `id` dynamic, static or kinematic.
+    pub fn set_body_kind(&mut self, id: BodyId, kind: BodyKind) {
+        if let Some(body) = self.get_body_mut(id) {
+            body.set_kind(kind);
+        }
+    }
+
+    /// Sets the velocity of the body with id `id`, e.g. to drive a kinematic body along a path.
+    pub fn set_body_velocity(&mut self, id: BodyId, velocity: Vector) {
+        if let Some(body) = self.get_body_mut(id).filter(|b| b.kind != BodyKind::Static) {
+            body.velocity = velocity;
         }
     }
 
+    /// Sets the velocity of the kinematic body with id `id` so it reaches `target` after a step
+    /// `dt`, for following a scripted path point by point.
+    pub fn move_kinematic_body(&mut self, id: BodyId, target: Vector, dt: Scalar) {
+        let boundary = self.boundary;
+        if let Some(body) = self.get_body_mut(id).filter(|b| b.kind == BodyKind::Kinematic) {
+            body.velocity = boundary.displacement(body.position, target) / dt;
+        }
+    }
+
+    /// Returns the current contact solver settings.
+    pub fn contact_solver_settings(&self) -> ContactSolverSettings {
+        self.contact_solver.settings
+    }
+
+    /// Sets the iteration count, position correction and warm starting of the contact solver.
+    /// Fails if a setting is out of range, e.g. zero iterations.
+    pub fn set_contact_solver_settings(&mut self, settings: ContactSolverSettings) -> Result<(), ConfigError> {
+        settings.validate()?;
+        self.contact_solver.settings = settings;
+        Ok(())
+    }
+
+    /// Sets the algorithm used to find candidate pairs for the collision pass.
+    pub fn set_broadphase(&mut self, broadphase: Broadphase) {
+        self.broadphase = broadphase;
+    }
+
+    /// Returns the RMS relative error of the accelerations from `solver` compared to direct
+    /// summation on the current state, e.g. to measure Barnes-Hut accuracy for a given theta.
+    pub fn gravity_error(&self, solver: GravitySolver) -> Scalar {
+        let exact = Gravity { solver: GravitySolver::Direct, ..self.gravity }.accelerations(&self.bodies, &self.boundary);
+        let approx = Gravity { solver, ..self.gravity }.accelerations(&self.bodies, &self.boundary);
+        gravity::relative_error(&approx, &exact)
+    }
+
+    /// Returns the total potential energy of mutual gravity (consistent with the softening kernel),
+    /// of the other force laws and of the external force fields.
+    pub fn potential_energy(&self) -> Scalar {
+        self.gravity.potential_energy(&self.bodies, &self.boundary)
+            + self.interaction_energy()
+            + self.external_potential_energy()
+    }
+
+    /// Returns the potential energy of all pairs of bodies under the force laws other than gravity.
+    fn interaction_energy(&self) -> Scalar {
+        self.force_laws.iter().map(|law| law.potential_energy(&self.bodies, &self.boundary)).sum()
+    }
+
+    /// Returns the potential energy of all bodies in the external force fields.
+    fn external_potential_energy(&self) -> Scalar {
+        self.force_fields
+            .iter()
+            .flat_map(|field| {
+                self.bodies
+                    .iter()
+                    .map(|body| body.mass * field.potential(body.position, &self.gravity, &self.boundary))
+            })
+            .sum()
+    }
+
+    /// Measures energies, momenta, center of mass and virial ratio of the current state. O(n²), as
+    /// the potential energies are summed directly.
+    pub fn diagnostics(&self) -> Diagnostics {
+        let gravitational_energy = self.gravity.potential_energy(&self.bodies, &self.boundary);
+        Diagnostics::measure(&self.bodies, gravitational_energy, self.interaction_energy(), self.external_potential_energy())
+    }
+
+    /// Returns the seed the simulation was created from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
     /// Returns a reference to the bodies in the simulation.
     pub fn get_bodies(&self) -> &[Body] {
         &self.bodies
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::scenario::{RandomSquare, Scenario};
+
+    fn crowded(broadphase: Broadphase, collision_mode: CollisionMode) -> Simulation {
+        let config = SimulationConfig {
+            seed: Some(7),
+            scenario: Scenario::RandomSquare(RandomSquare { num_bodies: 400, size: 60.0, ..RandomSquare::default() }),
+            collision_mode,
+            broadphase,
+            ..SimulationConfig::default()
+        };
+        Simulation::from_config(&config).unwrap()
+    }
+
+    fn state(simulation: &Simulation) -> Vec<(BodyId, Vector, Vector, Scalar, Scalar)> {
+        simulation.get_bodies().iter().map(|b| (b.id, b.position, b.velocity, b.mass, b.radius)).collect()
+    }
+
+    #[test]
+    fn merging_does_not_depend_on_the_broadphase() {
+        let mut brute_force = crowded(Broadphase::BruteForce, CollisionMode::Merge);
+        let mut spatial_hash = crowded(Broadphase::SpatialHash, CollisionMode::Merge);
+        for _ in 0..5 {
+            brute_force.update(0.002);
+            spatial_hash.update(0.002);
+            assert_eq!(state(&brute_force), state(&spatial_hash));
+        }
+        assert!(brute_force.get_bodies().len() < 400);
+    }
+
+    #[cfg_attr(feature = "f64", allow(clippy::useless_conversion))] // bits are already u64 with f64
+    fn bits(simulation: &Simulation) -> Vec<u64> {
+        let bodies = simulation.get_bodies();
+        let values = bodies.iter().flat_map(|b| [b.position.axes(), b.velocity.axes()].concat());
+        values.map(|v| u64::from(v.to_bits())).collect()
+    }
+
+    #[test]
+    fn same_seed_gives_bit_identical_runs() {
+        let run = |seed| {
+            let mut simulation = Simulation::with_seed(seed);
+            simulation.set_collision_mode(CollisionMode::Fragment(Fragmentation::default())).unwrap();
+            for _ in 0..20 {
+                simulation.update(0.01);
+            }
+            simulation
+        };
+
+        let ids = |simulation: &Simulation| simulation.get_bodies().iter().map(|b| b.id).collect::<Vec<_>>();
+
+        let (a, b) = (run(42), run(42));
+        assert_eq!(bits(&a), bits(&b));
+        assert_eq!(ids(&a), ids(&b));
+        assert_ne!(bits(&a), bits(&run(43)));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn thread_count_does_not_change_the_result() {
+        let run = |threads, solver| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            pool.install(|| {
+                let mut simulation = Simulation::with_seed(42);
+                simulation.set_gravity_solver(solver).unwrap();
+                simulation.set_collision_mode(CollisionMode::Fragment(Fragmentation::default())).unwrap();
+                for _ in 0..20 {
+                    simulation.update(0.01);
+                }
+                bits(&simulation)
+            })
+        };
+        for solver in [GravitySolver::Direct, GravitySolver::BarnesHut { theta: 0.5 }] {
+            assert_eq!(run(1, solver), run(4, solver));
+        }
+    }
+
+    #[test]
+    fn static_bodies_added_with_a_velocity_stay_put() {
+        let mut simulation = crowded(Broadphase::SpatialHash, CollisionMode::Merge);
+        let mut body = Body::new(Vector::splat(100.0), Vector::unit(0), 3.0, 1.0);
+        body.kind = BodyKind::Static;
+        let id = simulation.add_body(body).unwrap();
+        for _ in 0..5 {
+            simulation.update(0.01);
+        }
+        let body = simulation.get_bodies().iter().find(|b| b.id == id).unwrap();
+        assert_eq!((body.position, body.velocity), (Vector::splat(100.0), Vector::zero()));
+    }
+
+    #[test]
+    fn body_arrays_follow_the_bodies() {
+        let mut simulation = crowded(Broadphase::SpatialHash, CollisionMode::Merge);
+        simulation.update(0.002);
+        simulation.add_body(Body::new(Vector::splat(100.0), Vector::unit(0), 3.0, 1.0)).unwrap();
+
+        let bodies = simulation.get_bodies().to_vec();
+        let arrays = simulation.body_arrays();
+        assert_eq!(arrays.len(), bodies.len());
+        for (i, body) in bodies.iter().enumerate() {
+            assert_eq!(arrays.position(i), body.position);
+            assert_eq!(arrays.mass[i], body.mass);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::simulation::body::Body;
+#[cfg(not(feature = "f64"))]
+use crate::simulation::parallel;
+#[cfg(not(feature = "f64"))]
+use crate::simulation::simd::F32x4;
+use crate::simulation::vec2::{Scalar, Vector};
+
+/// Number of bodies processed at once by the kernel.
+const LANES: usize = 4;
+
+/// Positions and masses of a set of bodies as separate arrays (structure of arrays), padded with
+/// massless bodies to a whole number of `LANES` so the kernel can load full lanes without checks.
+///
+/// Kept by the simulation across steps and synced in place, so the arrays are only allocated again
+/// when the number of bodies grows.
+#[derive(Clone, Debug, Default)]
+pub struct BodyArrays {
+    /// Number of bodies, without the padding.
+    len: usize,
+    /// One array per axis.
+    pub position: [Vec<Scalar>; Vector::DIM],
+    pub mass: Vec<Scalar>,
+}
+
+impl BodyArrays {
+    pub fn new(bodies: &[Body]) -> Self {
+        let mut arrays = BodyArrays::default();
+        arrays.sync(bodies);
+        arrays
+    }
+
+    /// Overwrites the arrays with the state of `bodies`, reusing their allocations.
+    pub fn sync(&mut self, bodies: &[Body]) {
+        let padded = bodies.len().next_multiple_of(LANES);
+        let fill = |array: &mut Vec<Scalar>, values: &mut dyn Iterator<Item = Scalar>| {
+            array.clear();
+            array.extend(values);
+            array.resize(padded, 0.0);
+        };
+        for k in 0..Vector::DIM {
+            fill(&mut self.position[k], &mut bodies.iter().map(|b| b.position.axes()[k]));
+        }
+        fill(&mut self.mass, &mut bodies.iter().map(|b| b.mass));
+        self.len = bodies.len();
+    }
+
+    /// Number of bodies, without the padding.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Position of body `i`.
+    pub fn position(&self, i: usize) -> Vector {
+        Vector::from_axes(std::array::from_fn(|k| self.position[k][i]))
+    }
+}
+
+/// Direct-sum gravitational accelerations of the bodies in `arrays` with Plummer softening of
+/// squared length `softening_sq` (0 for unsoftened gravity), in an open domain.
+///
+/// There is no branch for the self-interaction: a body's own term has zero displacement, so it
+/// contributes nothing (coincident bodies without softening give a factor of 0, as in the scalar
+/// kernel). Results match the scalar kernel up to rounding from the different summation order.
+#[cfg(not(feature = "f64"))]
+pub fn direct_accelerations(arrays: &BodyArrays, grav_constant: f32, softening_sq: f32) -> Vec<Vector> {
+    parallel::map_indices(arrays.len(), |i| {
+        accumulate(arrays, arrays.position(i), softening_sq) * grav_constant
+    })
+}
+
+/// Sum of `m d / (|d|² + softening_sq)^(3/2)` over all bodies, for displacements `d` from `position`.
+#[cfg(not(feature = "f64"))]
+fn accumulate(arrays: &BodyArrays, position: Vector, softening_sq: f32) -> Vector {
+    let p = position.axes().map(F32x4::splat);
+    let softening_sq = F32x4::splat(softening_sq);
+    let mut accel = [F32x4::splat(0.0); Vector::DIM];
+    let positions = arrays.position.each_ref().map(|axis| axis.as_chunks::<LANES>().0);
+    for (chunk, mass) in arrays.mass.as_chunks::<LANES>().0.iter().enumerate() {
+        let d: [F32x4; Vector::DIM] = std::array::from_fn(|k| F32x4::load(&positions[k][chunk]) - p[k]);
+        let s = d[1..].iter().fold(d[0] * d[0], |s, &dk| s + dk * dk) + softening_sq;
+        // zero separation (self term, coincident bodies) divides by zero, those lanes are dropped
+        let factor = (F32x4::load(mass) / (s * s.sqrt())).where_positive(s);
+        for (a, dk) in accel.iter_mut().zip(d) {
+            *a = *a + dk * factor;
+        }
+    }
+    Vector::from_axes(accel.map(F32x4::sum))
+}
@@ -0,0 +1,89 @@
+use serde::Deserialize;
+
+use crate::simulation::config::{self, ConfigError};
+use crate::simulation::vec2::Scalar;
+
+/// Gravitational softening kernel, smoothing the interaction at separations below `length` so
+/// close encounters don't produce unbounded forces.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+pub enum Softening {
+    /// Unsoftened Newtonian gravity, singular at zero separation.
+    None,
+    /// Plummer softening, potential -1 / sqrt(r² + length²). Softens at all separations.
+    Plummer { length: Scalar },
+    /// Compact cubic spline kernel (Monaghan & Lattanzio, as used in Gadget). Exactly Newtonian
+    /// beyond `length`, with a finite force and potential inside.
+    Spline { length: Scalar },
+}
+
+impl Softening {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        match *self {
+            Softening::None => Ok(()),
+            Softening::Plummer { length } | Softening::Spline { length } => {
+                config::check(config::non_negative(length), "softening.length", "must be finite and non-negative")
+            }
+        }
+    }
+
+    /// Returns `k` such that the acceleration towards a unit point mass (with G = 1) at
+    /// displacement `d` is `d * k`. Equal to 1 / r³ for unsoftened gravity.
+    pub fn force_factor(&self, distance_sq: Scalar) -> Scalar {
+        match *self {
+            Softening::None => {
+                if distance_sq > 0.0 {
+                    1.0 / (distance_sq * distance_sq.sqrt())
+                } else {
+                    0.0
+                }
+            }
+            Softening::Plummer { length } => {
+                let s = distance_sq + length * length;
+                if s > 0.0 { 1.0 / (s * s.sqrt()) } else { 0.0 }
+            }
+            Softening::Spline { length } => {
+                let r = distance_sq.sqrt();
+                if r >= length {
+                    return Softening::None.force_factor(distance_sq);
+                }
+                let h_inv = 1.0 / length;
+                let h3_inv = h_inv * h_inv * h_inv;
+                let u = r * h_inv;
+                if u < 0.5 {
+                    h3_inv * (32.0 / 3.0 + u * u * (32.0 * u - 38.4))
+                } else {
+                    h3_inv * (64.0 / 3.0 - 48.0 * u + 38.4 * u * u - (32.0 / 3.0) * u * u * u - (1.0 / 15.0) / (u * u * u))
+                }
+            }
+        }
+    }
+
+    /// Returns the potential of a unit point mass (with G = 1) at squared distance `distance_sq`.
+    /// Equal to -1 / r for unsoftened gravity.
+    pub fn potential(&self, distance_sq: Scalar) -> Scalar {
+        match *self {
+            Softening::None => {
+                if distance_sq > 0.0 { -1.0 / distance_sq.sqrt() } else { 0.0 }
+            }
+            Softening::Plummer { length } => {
+                let s = distance_sq + length * length;
+                if s > 0.0 { -1.0 / s.sqrt() } else { 0.0 }
+            }
+            Softening::Spline { length } => {
+                let r = distance_sq.sqrt();
+                if r >= length {
+                    return Softening::None.potential(distance_sq);
+                }
+                let h_inv = 1.0 / length;
+                let u = r * h_inv;
+                let u2 = u * u;
+                if u < 0.5 {
+                    h_inv * (-2.8 + u2 * (16.0 / 3.0 + u2 * (6.4 * u - 9.6)))
+                } else {
+                    h_inv * (-3.2 + (1.0 / 15.0) / u + u2 * (32.0 / 3.0 + u * (-16.0 + u * (9.6 - (32.0 / 15.0) * u))))
+                }
+            }
+        }
+    }
+}
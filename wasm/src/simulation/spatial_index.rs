@@ -0,0 +1,359 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::simulation::body::Body;
+use crate::simulation::boundary::Boundary;
+use crate::simulation::vec2::{Scalar, Vector};
+
+/// Nodes with at most this many bodies are not split further.
+const LEAF_SIZE: usize = 8;
+
+/// A node of the k-d tree, covering the bodies `indices[start..end]`.
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    // bounding box of the body centers
+    min: Vector,
+    max: Vector,
+    // largest body radius, so bodies reaching outside the box are still found
+    max_radius: Scalar,
+    start: usize,
+    end: usize,
+    // index of the first of two contiguous children, if split
+    children: Option<usize>,
+}
+
+/// An entry of the best-first search for nearest neighbours.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Entry {
+    Node(usize),
+    Body(usize),
+}
+
+/// k-d tree over body centers for spatial queries. Queries measure distances to the nearest
+/// periodic image, so they find bodies across the edges of a periodic domain.
+pub struct SpatialIndex {
+    nodes: Vec<Node>,
+    // body indices, ordered so every node covers a contiguous range
+    indices: Vec<usize>,
+}
+
+impl SpatialIndex {
+    /// Builds a k-d tree over all given bodies, splitting nodes at the median of their wider axis.
+    pub fn new(bodies: &[Body]) -> Self {
+        let mut index = SpatialIndex {
+            nodes: vec![],
+            indices: (0..bodies.len()).collect(),
+        };
+        index.nodes.push(index.node(bodies, 0, bodies.len()));
+
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = index.nodes[node_index];
+            if node.end - node.start <= LEAF_SIZE {
+                continue;
+            }
+            let extent = (node.max - node.min).axes();
+            let axis = (1..Vector::DIM).fold(0, |widest, k| if extent[k] > extent[widest] { k } else { widest });
+            let key = |i: &usize| bodies[*i].position.axes()[axis];
+            let mid = (node.start + node.end) / 2;
+            index.indices[node.start..node.end]
+                .select_nth_unstable_by(mid - node.start, |a, b| key(a).total_cmp(&key(b)));
+
+            let children = index.nodes.len();
+            index.nodes[node_index].children = Some(children);
+            index.nodes.push(index.node(bodies, node.start, mid));
+            index.nodes.push(index.node(bodies, mid, node.end));
+            stack.extend([children, children + 1]);
+        }
+        index
+    }
+
+    /// Leaf node covering `indices[start..end]`.
+    fn node(&self, bodies: &[Body], start: usize, end: usize) -> Node {
+        let mut node = Node {
+            min: Vector::splat(Scalar::INFINITY),
+            max: Vector::splat(Scalar::NEG_INFINITY),
+            max_radius: 0.0,
+            start,
+            end,
+            children: None,
+        };
+        for &i in &self.indices[start..end] {
+            let body = &bodies[i];
+            node.min = node.min.min(body.position);
+            node.max = node.max.max(body.position);
+            node.max_radius = node.max_radius.max(body.radius);
+        }
+        node
+    }
+
+    /// Calls `visit` with the index of every body in a node not rejected by `keep`.
+    fn for_each_candidate(&self, keep: impl Fn(&Node) -> bool, mut visit: impl FnMut(usize)) {
+        if self.indices.is_empty() {
+            return;
+        }
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if !keep(node) {
+                continue;
+            }
+            match node.children {
+                Some(children) => stack.extend([children, children + 1]),
+                None => self.indices[node.start..node.end].iter().for_each(|&i| visit(i)),
+            }
+        }
+    }
+
+    /// Returns the body whose disc (sphere in 3D) contains `point`, the one with the closest center if several do.
+    pub fn body_at(&self, bodies: &[Body], boundary: &Boundary, point: Vector) -> Option<usize> {
+        let mut best: Option<(usize, Scalar)> = None;
+        self.for_each_candidate(
+            |node| gap(node, point, boundary).length_squared() <= node.max_radius * node.max_radius,
+            |i| {
+                let body = &bodies[i];
+                let distance_sq = boundary.displacement(point, body.position).length_squared();
+                if distance_sq <= body.radius * body.radius && best.is_none_or(|(_, d)| distance_sq < d) {
+                    best = Some((i, distance_sq));
+                }
+            },
+        );
+        best.map(|(i, _)| i)
+    }
+
+    /// Returns the bodies with centers within `radius` of `center`.
+    pub fn within(&self, bodies: &[Body], boundary: &Boundary, center: Vector, radius: Scalar) -> Vec<usize> {
+        let radius_sq = radius * radius;
+        let mut found = vec![];
+        self.for_each_candidate(
+            |node| gap(node, center, boundary).length_squared() <= radius_sq,
+            |i| {
+                if boundary.displacement(center, bodies[i].position).length_squared() <= radius_sq {
+                    found.push(i);
+                }
+            },
+        );
+        found
+    }
+
+    /// Returns the bodies with centers inside the axis-aligned box from `min` to `max`.
+    pub fn in_rect(&self, bodies: &[Body], boundary: &Boundary, min: Vector, max: Vector) -> Vec<usize> {
+        // a body is inside if its offset from the box center is within the half extents
+        let (center, half) = ((min + max) / 2.0, ((max - min) / 2.0).axes());
+        let within = |offset: Vector| offset.axes().into_iter().zip(half).all(|(d, h)| d.abs() <= h);
+        let mut found = vec![];
+        self.for_each_candidate(
+            |node| within(gap(node, center, boundary)),
+            |i| {
+                if within(boundary.displacement(center, bodies[i].position)) {
+                    found.push(i);
+                }
+            },
+        );
+        found
+    }
+
+    /// Returns the bodies with centers inside every half-space `normal · p >= offset` of `planes`,
+    /// e.g. a view frustum. Ignores periodic images.
+    pub fn in_planes(&self, bodies: &[Body], planes: &[(Vector, Scalar)]) -> Vec<usize> {
+        let inside = |point: Vector| planes.iter().all(|&(normal, offset)| normal.dot(point) >= offset);
+        let mut found = vec![];
+        self.for_each_candidate(
+            // the box reaches into a half-space if its corner farthest along the normal does
+            |node| {
+                let (lo, hi) = (node.min.axes(), node.max.axes());
+                planes.iter().all(|&(normal, offset)| {
+                    let n = normal.axes();
+                    let corner = Vector::from_axes(std::array::from_fn(|k| if n[k] > 0.0 { hi[k] } else { lo[k] }));
+                    normal.dot(corner) >= offset
+                })
+            },
+            |i| {
+                if inside(bodies[i].position) {
+                    found.push(i);
+                }
+            },
+        );
+        found
+    }
+
+    /// Returns the body whose surface the ray from `origin` along the unit vector `direction` hits
+    /// first (or that contains `origin`). Ignores periodic images.
+    pub fn first_hit(&self, bodies: &[Body], origin: Vector, direction: Vector) -> Option<usize> {
+        let mut best: Option<(usize, Scalar)> = None;
+        self.for_each_candidate(
+            |node| {
+                let margin = Vector::splat(node.max_radius);
+                ray_hits_box(origin, direction, node.min - margin, node.max + margin)
+            },
+            |i| {
+                let hit = ray_hits_sphere(origin, direction, bodies[i].position, bodies[i].radius);
+                if let Some(distance) = hit && best.is_none_or(|(_, d)| distance < d) {
+                    best = Some((i, distance));
+                }
+            },
+        );
+        best.map(|(i, _)| i)
+    }
+
+    /// Returns the `k` bodies with centers nearest to `point`, closest first.
+    pub fn nearest(&self, bodies: &[Body], boundary: &Boundary, point: Vector, k: usize) -> Vec<usize> {
+        let mut found = Vec::with_capacity(k.min(self.indices.len()));
+        if self.indices.is_empty() || k == 0 {
+            return found;
+        }
+        // best-first search, popping nodes and bodies by (lower bound of) distance; squared
+        // distances are non-negative, so their bit patterns order the same as their values
+        let mut queue = BinaryHeap::new();
+        queue.push(Reverse((0, Entry::Node(0))));
+        while let Some(Reverse((_, entry))) = queue.pop() {
+            match entry {
+                Entry::Body(i) => {
+                    found.push(i);
+                    if found.len() == k {
+                        break;
+                    }
+                }
+                Entry::Node(node_index) => {
+                    let node = &self.nodes[node_index];
+                    match node.children {
+                        Some(children) => {
+                            for child in [children, children + 1] {
+                                let distance_sq = gap(&self.nodes[child], point, boundary).length_squared();
+                                queue.push(Reverse((distance_sq.to_bits(), Entry::Node(child))));
+                            }
+                        }
+                        None => {
+                            for &i in &self.indices[node.start..node.end] {
+                                let distance_sq = boundary.displacement(point, bodies[i].position).length_squared();
+                                queue.push(Reverse((distance_sq.to_bits(), Entry::Body(i))));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        found
+    }
+}
+
+/// Per-axis distance from `point` to the bounding box of `node`, 0 on axes where it is inside.
+fn gap(node: &Node, point: Vector, boundary: &Boundary) -> Vector {
+    let (p, lo, hi) = (point.axes(), node.min.axes(), node.max.axes());
+    match *boundary {
+        Boundary::Periodic { min, max } => {
+            let size = (max - min).axes();
+            Vector::from_axes(std::array::from_fn(|k| periodic_gap(p[k], lo[k], hi[k], size[k])))
+        }
+        _ => Vector::from_axes(std::array::from_fn(|k| (lo[k] - p[k]).max(p[k] - hi[k]).max(0.0))),
+    }
+}
+
+/// Distance from `p` to the interval `lo..=hi` on an axis wrapping around every `period`.
+fn periodic_gap(p: Scalar, lo: Scalar, hi: Scalar, period: Scalar) -> Scalar {
+    if (p - lo).rem_euclid(period) <= hi - lo {
+        0.0
+    } else {
+        (lo - p).rem_euclid(period).min((p - hi).rem_euclid(period))
+    }
+}
+
+/// Returns whether the ray from `origin` along `direction` passes through the box from `min` to
+/// `max` (slab test).
+fn ray_hits_box(origin: Vector, direction: Vector, min: Vector, max: Vector) -> bool {
+    let (o, d, lo, hi) = (origin.axes(), direction.axes(), min.axes(), max.axes());
+    let (mut enter, mut exit) = (0.0 as Scalar, Scalar::INFINITY);
+    for k in 0..Vector::DIM {
+        if d[k] == 0.0 {
+            if o[k] < lo[k] || o[k] > hi[k] {
+                return false;
+            }
+            continue;
+        }
+        let (t0, t1) = ((lo[k] - o[k]) / d[k], (hi[k] - o[k]) / d[k]);
+        enter = enter.max(t0.min(t1));
+        exit = exit.min(t0.max(t1));
+    }
+    enter <= exit
+}
+
+/// Distance along the ray from `origin` along the unit vector `direction` to where it enters the
+/// sphere, 0 if `origin` is inside and `None` if it misses.
+fn ray_hits_sphere(origin: Vector, direction: Vector, center: Vector, radius: Scalar) -> Option<Scalar> {
+    let offset = center - origin;
+    let excess = offset.length_squared() - radius * radius;
+    if excess <= 0.0 {
+        return Some(0.0);
+    }
+    let along = offset.dot(direction);
+    let discriminant = along * along - excess;
+    (along > 0.0 && discriminant >= 0.0).then(|| along - discriminant.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    /// Bodies of mixed sizes scattered over the square of side 40 centered on the origin, wrapped
+    /// into `boundary`.
+    fn scattered_bodies(boundary: &Boundary) -> Vec<Body> {
+        let mut rng = StdRng::seed_from_u64(5);
+        (0..300)
+            .map(|_| {
+                let position = Vector::from_axes(std::array::from_fn(|_| rng.random_range(-20.0..20.0)));
+                Body::new(boundary.wrap(position), Vector::zero(), 1.0, rng.random_range(0.2..2.0))
+            })
+            .collect()
+    }
+
+    fn assert_matches_linear_scan(boundary: &Boundary) {
+        let bodies = scattered_bodies(boundary);
+        let index = SpatialIndex::new(&bodies);
+        let distance_sq = |point: Vector, i: usize| boundary.displacement(point, bodies[i].position).length_squared();
+        let scan = |keep: &dyn Fn(usize) -> bool| (0..bodies.len()).filter(|&i| keep(i)).collect::<Vec<_>>();
+        let sorted = |mut found: Vec<usize>| {
+            found.sort();
+            found
+        };
+
+        // points near the edges, where periodic queries reach across, and next to bodies
+        let mut points = vec![Vector::splat(19.5), Vector::splat(-19.8), Vector::zero()];
+        points.extend(bodies.iter().step_by(30).map(|b| b.position + Vector::splat(0.1)));
+        for point in points {
+            let expected = scan(&|i| distance_sq(point, i) <= 25.0);
+            assert_eq!(sorted(index.within(&bodies, boundary, point, 5.0)), expected);
+
+            let (min, max) = (point - Vector::splat(4.0), point + Vector::splat(3.0));
+            let (center, half) = ((min + max) / 2.0, Vector::splat(3.5));
+            let inside = |i: usize| {
+                let offset = boundary.displacement(center, bodies[i].position).axes();
+                offset.into_iter().zip(half.axes()).all(|(d, h)| d.abs() <= h)
+            };
+            assert_eq!(sorted(index.in_rect(&bodies, boundary, min, max)), scan(&inside));
+
+            // ties make the order of equally distant bodies arbitrary, so compare distances
+            let mut expected: Vec<Scalar> = (0..bodies.len()).map(|i| distance_sq(point, i)).collect();
+            expected.sort_by(Scalar::total_cmp);
+            let nearest = index.nearest(&bodies, boundary, point, 10);
+            assert_eq!(nearest.iter().map(|&i| distance_sq(point, i)).collect::<Vec<_>>(), expected[..10]);
+
+            let containing = scan(&|i| distance_sq(point, i) <= bodies[i].radius * bodies[i].radius);
+            let closest = containing.into_iter().map(|i| distance_sq(point, i)).min_by(Scalar::total_cmp);
+            let found = index.body_at(&bodies, boundary, point);
+            assert_eq!(found.map(|i| distance_sq(point, i)), closest);
+        }
+    }
+
+    #[test]
+    fn queries_match_a_linear_scan() {
+        assert_matches_linear_scan(&Boundary::Open);
+    }
+
+    #[test]
+    fn queries_match_a_linear_scan_across_periodic_edges() {
+        assert_matches_linear_scan(&Boundary::Periodic { min: Vector::splat(-20.0), max: Vector::splat(20.0) });
+    }
+}
@@ -1,29 +1,103 @@
 use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
 
 use std::ops::{Add, Sub, Mul, Div, AddAssign, SubAssign, MulAssign, DivAssign};
 
+/// Floating point type of the simulation, f64 with the `f64` feature for long integrations and
+/// large spatial extents. The renderer converts to f32 when uploading bodies.
+#[cfg(not(feature = "f64"))]
+pub type Scalar = f32;
+#[cfg(feature = "f64")]
+pub type Scalar = f64;
+
+/// Mathematical constants of type `Scalar`.
+#[cfg(not(feature = "f64"))]
+pub use std::f32::consts;
+#[cfg(feature = "f64")]
+pub use std::f64::consts;
+
+/// Position and velocity type of the simulation, `Vec3` with the `dim3` feature.
+#[cfg(not(feature = "dim3"))]
+pub type Vector = Vec2;
+#[cfg(feature = "dim3")]
+pub type Vector = crate::simulation::vec3::Vec3;
+
+/// Angular velocity and angular momentum type: a scalar about the z axis in 2D, a vector in 3D.
+#[cfg(not(feature = "dim3"))]
+pub type Spin = Scalar;
+#[cfg(feature = "dim3")]
+pub type Spin = crate::simulation::vec3::Vec3;
+
 /// A 2D vector struct with basic operations.
 #[repr(C)]
-#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Pod, Zeroable, Deserialize, Serialize)]
 pub struct Vec2 {
-    pub x: f32,
-    pub y: f32,
+    pub x: Scalar,
+    pub y: Scalar,
 }
 
 impl Vec2 {
-    pub fn new(x: f32, y: f32) -> Self {
+    /// Number of components.
+    pub const DIM: usize = 2;
+
+    pub fn new(x: Scalar, y: Scalar) -> Self {
         Vec2 { x, y }
     }
     pub fn zero() -> Self {
         Vec2 { x: 0.0, y: 0.0 }
     }
-    pub fn dot(self, other: Vec2) -> f32 {
+    pub fn splat(value: Scalar) -> Self {
+        Vec2 { x: value, y: value }
+    }
+    /// Vector in the xy plane, the same as `new` in 2D.
+    pub fn planar(x: Scalar, y: Scalar) -> Self {
+        Vec2 { x, y }
+    }
+    /// Unit vector along `axis` (0 = x, 1 = y).
+    pub fn unit(axis: usize) -> Self {
+        Vec2::from_axes(std::array::from_fn(|k| if k == axis { 1.0 } else { 0.0 }))
+    }
+    pub fn from_axes(axes: [Scalar; 2]) -> Self {
+        Vec2 { x: axes[0], y: axes[1] }
+    }
+    pub fn axes(self) -> [Scalar; 2] {
+        [self.x, self.y]
+    }
+    /// Component-wise minimum.
+    pub fn min(self, other: Vec2) -> Self {
+        Vec2::new(self.x.min(other.x), self.y.min(other.y))
+    }
+    /// Component-wise maximum.
+    pub fn max(self, other: Vec2) -> Self {
+        Vec2::new(self.x.max(other.x), self.y.max(other.y))
+    }
+    pub fn dot(self, other: Vec2) -> Scalar {
         self.x * other.x + self.y * other.y
     }
-    pub fn length(self) -> f32 {
+    /// Z component of the 3D cross product (2D "perp dot" product).
+    pub fn cross(self, other: Vec2) -> Scalar {
+        self.x * other.y - self.y * other.x
+    }
+    /// Rotates the vector by 90 degrees counter-clockwise, so that `w x r` is `r.perp() * w`.
+    pub fn perp(self) -> Self {
+        Vec2::new(-self.y, self.x)
+    }
+    /// Velocity of the point at this offset from the center of a body spinning at `spin`.
+    pub fn spin_velocity(self, spin: Scalar) -> Self {
+        self.perp() * spin
+    }
+    /// Unit tangent of this unit normal.
+    pub fn tangents(self) -> [Self; 1] {
+        [self.perp()]
+    }
+    /// Unit vector at `angle` from the x axis. `_axis` is the rotation axis, always z in 2D.
+    pub fn on_circle(_axis: Scalar, angle: Scalar) -> Self {
+        Vec2::new(angle.cos(), angle.sin())
+    }
+    pub fn length(self) -> Scalar {
         self.dot(self).sqrt()
     }
-    pub fn length_squared(self) -> f32 {
+    pub fn length_squared(self) -> Scalar {
         self.dot(self)
     }
     pub fn normalize(self) -> Self {
@@ -48,15 +122,15 @@ impl Sub for Vec2 {
         Vec2::new(self.x - other.x, self.y - other.y)
     }
 }
-impl Mul<f32> for Vec2 {
+impl Mul<Scalar> for Vec2 {
     type Output = Self;
-    fn mul(self, scalar: f32) -> Self {
+    fn mul(self, scalar: Scalar) -> Self {
         Vec2::new(self.x * scalar, self.y * scalar)
     }
 }
-impl Div<f32> for Vec2 {
+impl Div<Scalar> for Vec2 {
     type Output = Self;
-    fn div(self, scalar: f32) -> Self {
+    fn div(self, scalar: Scalar) -> Self {
         Vec2::new(self.x / scalar, self.y / scalar)
     }
 }
@@ -72,14 +146,14 @@ impl SubAssign for Vec2 {
         self.y -= other.y;
     }
 }
-impl MulAssign<f32> for Vec2 {
-    fn mul_assign(&mut self, scalar: f32) {
+impl MulAssign<Scalar> for Vec2 {
+    fn mul_assign(&mut self, scalar: Scalar) {
         self.x *= scalar;
         self.y *= scalar;
     }
 }
-impl DivAssign<f32> for Vec2 {
-    fn div_assign(&mut self, scalar: f32) {
+impl DivAssign<Scalar> for Vec2 {
+    fn div_assign(&mut self, scalar: Scalar) {
         self.x /= scalar;
         self.y /= scalar;
     }
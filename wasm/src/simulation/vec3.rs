@@ -0,0 +1,161 @@
+use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
+
+use std::ops::{Add, Sub, Mul, Div, AddAssign, SubAssign, MulAssign, DivAssign};
+
+use crate::simulation::vec2::Scalar;
+
+/// A 3D vector struct with the same operations as `Vec2`, used with the `dim3` feature. The z
+/// component may be left out when deserializing, so 2D configs load in the xy plane.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Pod, Zeroable, Deserialize, Serialize)]
+pub struct Vec3 {
+    pub x: Scalar,
+    pub y: Scalar,
+    #[serde(default)]
+    pub z: Scalar,
+}
+
+impl Vec3 {
+    /// Number of components.
+    pub const DIM: usize = 3;
+
+    pub fn new(x: Scalar, y: Scalar, z: Scalar) -> Self {
+        Vec3 { x, y, z }
+    }
+    pub fn zero() -> Self {
+        Vec3 { x: 0.0, y: 0.0, z: 0.0 }
+    }
+    pub fn splat(value: Scalar) -> Self {
+        Vec3 { x: value, y: value, z: value }
+    }
+    /// Vector in the xy plane (z = 0).
+    pub fn planar(x: Scalar, y: Scalar) -> Self {
+        Vec3 { x, y, z: 0.0 }
+    }
+    /// Unit vector along `axis` (0 = x, 1 = y, 2 = z).
+    pub fn unit(axis: usize) -> Self {
+        Vec3::from_axes(std::array::from_fn(|k| if k == axis { 1.0 } else { 0.0 }))
+    }
+    pub fn from_axes(axes: [Scalar; 3]) -> Self {
+        Vec3 { x: axes[0], y: axes[1], z: axes[2] }
+    }
+    pub fn axes(self) -> [Scalar; 3] {
+        [self.x, self.y, self.z]
+    }
+    /// Component-wise minimum.
+    pub fn min(self, other: Vec3) -> Self {
+        Vec3::new(self.x.min(other.x), self.y.min(other.y), self.z.min(other.z))
+    }
+    /// Component-wise maximum.
+    pub fn max(self, other: Vec3) -> Self {
+        Vec3::new(self.x.max(other.x), self.y.max(other.y), self.z.max(other.z))
+    }
+    pub fn dot(self, other: Vec3) -> Scalar {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+    pub fn cross(self, other: Vec3) -> Vec3 {
+        Vec3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+    /// Rotates the vector by 90 degrees counter-clockwise about the z axis.
+    pub fn perp(self) -> Self {
+        Vec3::new(-self.y, self.x, self.z)
+    }
+    /// Rotates the vector by `angle` about the x axis.
+    pub fn rotate_x(self, angle: Scalar) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Vec3::new(self.x, self.y * cos - self.z * sin, self.y * sin + self.z * cos)
+    }
+    /// Velocity of the point at this offset from the center of a body spinning at `spin`.
+    pub fn spin_velocity(self, spin: Vec3) -> Self {
+        spin.cross(self)
+    }
+    /// Two unit tangents of this unit normal, perpendicular to each other.
+    pub fn tangents(self) -> [Self; 2] {
+        // cross with the axis least aligned with the normal, so the tangent never degenerates
+        let first = if self.x.abs() >= 0.57735 {
+            Vec3::new(self.y, -self.x, 0.0)
+        } else {
+            Vec3::new(0.0, self.z, -self.y)
+        }
+        .normalize();
+        [first, self.cross(first)]
+    }
+    /// Unit vector at `angle` on the circle perpendicular to `axis` (the z axis if `axis` is zero).
+    pub fn on_circle(axis: Vec3, angle: Scalar) -> Self {
+        let axis = if axis.length_squared() > 0.0 { axis.normalize() } else { Vec3::unit(2) };
+        let [u, v] = axis.tangents();
+        u * angle.cos() + v * angle.sin()
+    }
+    pub fn length(self) -> Scalar {
+        self.dot(self).sqrt()
+    }
+    pub fn length_squared(self) -> Scalar {
+        self.dot(self)
+    }
+    pub fn normalize(self) -> Self {
+        let len = self.length();
+        if len > 0.0 {
+            Vec3::new(self.x / len, self.y / len, self.z / len)
+        } else {
+            Vec3::zero()
+        }
+    }
+}
+
+impl Add for Vec3 {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Vec3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+impl Sub for Vec3 {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Vec3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+impl Mul<Scalar> for Vec3 {
+    type Output = Self;
+    fn mul(self, scalar: Scalar) -> Self {
+        Vec3::new(self.x * scalar, self.y * scalar, self.z * scalar)
+    }
+}
+impl Div<Scalar> for Vec3 {
+    type Output = Self;
+    fn div(self, scalar: Scalar) -> Self {
+        Vec3::new(self.x / scalar, self.y / scalar, self.z / scalar)
+    }
+}
+impl AddAssign for Vec3 {
+    fn add_assign(&mut self, other: Self) {
+        self.x += other.x;
+        self.y += other.y;
+        self.z += other.z;
+    }
+}
+impl SubAssign for Vec3 {
+    fn sub_assign(&mut self, other: Self) {
+        self.x -= other.x;
+        self.y -= other.y;
+        self.z -= other.z;
+    }
+}
+impl MulAssign<Scalar> for Vec3 {
+    fn mul_assign(&mut self, scalar: Scalar) {
+        self.x *= scalar;
+        self.y *= scalar;
+        self.z *= scalar;
+    }
+}
+impl DivAssign<Scalar> for Vec3 {
+    fn div_assign(&mut self, scalar: Scalar) {
+        self.x /= scalar;
+        self.y /= scalar;
+        self.z /= scalar;
+    }
+}